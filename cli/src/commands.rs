@@ -0,0 +1,520 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    process::ExitCode,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, FixedOffset};
+use program_ingester::{
+    baseline::Baseline,
+    delta::Delta,
+    earned_value::{earned_value, EarnedValue, FeatureEarnedValue},
+    health::{Clock, FixedClock, HealthReport, SystemClock},
+    ics::IcsFormat,
+    input::{FeatureID, FeatureKind, RawFeature},
+    output::{Feature, ProgramGraph},
+    render::{Grouping, RenderOptions, Renderer, Renderers, Terminal},
+    simulation::Simulation,
+    source::{Formats, LineFormat},
+    stats::Stats,
+    symbol::Symbol,
+    validate::validate,
+    wbs::WbsCode,
+};
+use serde::Serialize;
+
+use crate::{
+    watch, ChartArgs, Command, Format, GroupBy, InputArgs, OutputArgs, QueryArgs, ReportArgs,
+    ScheduleArgs, ScheduleReport, UsageError,
+};
+
+/// ANSI escape codes to clear the terminal and move the cursor to the top left
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Run the command, returning the exit code for a run that did not fail
+pub fn run(command: Command) -> anyhow::Result<ExitCode> {
+    match command {
+        Command::Render {
+            input,
+            format,
+            chart,
+            output,
+            watch,
+        } => {
+            let renderers = Renderers::default();
+            let renderer = pick_renderer(&renderers, format.as_deref(), &output);
+            match watch.watch {
+                true => {
+                    let interval = std::time::Duration::from_millis(watch.interval);
+                    watch::poll(&input.inputs, interval, || {
+                        let features = read_features(&input)?;
+                        for issue in validate(&features) {
+                            tracing::warn!("{issue}");
+                        }
+                        // start from a clear screen, so the chart is redrawn in place
+                        // (the escape codes would end up in the exported documents of the other renderers)
+                        if output.output.is_none() && renderer.name() == Terminal.name() {
+                            print!("{CLEAR_SCREEN}");
+                        }
+                        render_chart(&ProgramGraph::from(features), renderer, &chart, &output)
+                    })
+                }
+                _ => render_chart(&read_graph(&input)?, renderer, &chart, &output)?,
+            }
+        }
+
+        Command::Validate { input, report } => {
+            let features = read_features(&input)?;
+            let issues = validate(&features);
+            emit(report.format, &report.output, &issues, |writer| {
+                if issues.is_empty() {
+                    writeln!(writer, "no problems found")?;
+                }
+                for issue in issues.iter() {
+                    writeln!(writer, "{issue}")?;
+                }
+                Ok(())
+            })?;
+            if !issues.is_empty() {
+                return Ok(ExitCode::from(1));
+            }
+        }
+
+        Command::Convert {
+            input,
+            format,
+            provenance,
+            output,
+        } => {
+            let graph = match provenance {
+                true => read_graph(&input)?,
+                false => read_graph(&input)?.without_provenance(),
+            };
+            emit(format, &output, &graph.with_provenance(), |writer| {
+                writeln!(writer, "{graph:#?}")
+            })?;
+        }
+
+        Command::Stats { input, report } => {
+            let stats = Stats::from(&read_graph(&input)?);
+            emit(report.format, &report.output, &stats, |writer| {
+                write!(writer, "{stats}")
+            })?;
+        }
+
+        Command::Diff {
+            old,
+            new,
+            input_format,
+            report,
+        } => {
+            let old = ProgramGraph::from(read_features(&InputArgs {
+                inputs: vec![old],
+                input_format: input_format.clone(),
+                as_of: None,
+                expand_from: None,
+                expand_until: None,
+            })?);
+            let new = ProgramGraph::from(read_features(&InputArgs {
+                inputs: vec![new],
+                input_format,
+                as_of: None,
+                expand_from: None,
+                expand_until: None,
+            })?);
+            let changeset = old.delta(&new);
+            emit(report.format, &report.output, &changeset, |writer| {
+                write!(writer, "{changeset}")
+            })?;
+        }
+
+        Command::Query {
+            input,
+            filter,
+            report,
+        } => {
+            let rows = query(&read_graph(&input)?, &filter);
+            emit(report.format, &report.output, &rows, |writer| {
+                for row in rows.iter() {
+                    writeln!(
+                        writer,
+                        "{} {} {} {} {} {} {} {}",
+                        row.program_id,
+                        row.wbs
+                            .as_ref()
+                            .map(ToString::to_string)
+                            .unwrap_or_default(),
+                        row.feature_id,
+                        row.assigned_team,
+                        row.progress_status,
+                        row.start_date.to_rfc3339(),
+                        row.end_date.to_rfc3339(),
+                        if row.kind == FeatureKind::Milestone {
+                            "milestone"
+                        } else {
+                            ""
+                        }
+                    )?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Command::Schedule {
+            input,
+            schedule,
+            report,
+        } => {
+            let graph = read_graph(&input)?;
+            run_schedule(&graph, &schedule, &report)?;
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The renderer named by `--format`, or else the one of the output file's extension, or else the terminal chart
+fn pick_renderer<'a>(
+    renderers: &'a Renderers,
+    format: Option<&str>,
+    output: &OutputArgs,
+) -> &'a dyn Renderer {
+    format
+        .and_then(|name| renderers.by_name(name))
+        .or_else(|| {
+            output
+                .output
+                .as_deref()
+                .and_then(|path| renderers.by_path(path))
+        })
+        .unwrap_or(&Terminal)
+}
+
+/// Draw the chart with the renderer, to the output file or STDOUT
+fn render_chart(
+    graph: &ProgramGraph,
+    renderer: &dyn Renderer,
+    chart: &ChartArgs,
+    output: &OutputArgs,
+) -> anyhow::Result<()> {
+    let options = RenderOptions {
+        width: chart.width,
+        from: chart.from,
+        until: chart.until,
+        group_by: match chart.group_by {
+            GroupBy::Program => Grouping::Program,
+            GroupBy::Team => Grouping::Team,
+        },
+        team: chart.team.clone(),
+        status: chart.status.clone(),
+        milestones: chart.milestones,
+        leaves: chart.leaves,
+        wbs: chart.wbs,
+    };
+
+    let mut writer = writer(output)?;
+    renderer.render(graph, &options, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn run_schedule(
+    graph: &ProgramGraph,
+    schedule: &ScheduleArgs,
+    report_args: &ReportArgs,
+) -> anyhow::Result<()> {
+    let status_date = schedule.status_date.unwrap_or_else(|| SystemClock.now());
+
+    match schedule.report {
+        ScheduleReport::Health => {
+            let report = HealthReport::new(graph, &FixedClock(status_date));
+            emit(report_args.format, &report_args.output, &report, |writer| {
+                writeln!(writer, "as of {}", report.as_of.to_rfc3339())?;
+                for (team, features) in report.teams.iter() {
+                    let flagged: Vec<_> =
+                        features.iter().filter(|f| f.health.is_flagged()).collect();
+                    if flagged.is_empty() {
+                        continue;
+                    }
+                    writeln!(writer, "{team}")?;
+                    for feature in flagged {
+                        writeln!(
+                            writer,
+                            "  {} {:?} (ends {})",
+                            feature.feature_id,
+                            feature.health,
+                            feature.end_date.to_rfc3339()
+                        )?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        ScheduleReport::EarnedValue => {
+            let programs = earned_value(graph, status_date);
+            emit(
+                report_args.format,
+                &report_args.output,
+                &programs,
+                |writer| {
+                    for program in programs.iter() {
+                        writeln!(
+                            writer,
+                            "{} {}",
+                            program.program_id,
+                            figures(&program.figures)
+                        )?;
+                        write_earned_value(writer, &program.root, 1)?;
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+
+        ScheduleReport::Variance => {
+            let path = schedule
+                .baseline
+                .as_deref()
+                .ok_or_else(|| UsageError("the variance report needs a --baseline".into()))?;
+            let file =
+                File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+            let report = Baseline::load(BufReader::new(file))?.compare(graph);
+            emit(report_args.format, &report_args.output, &report, |writer| {
+                writeln!(writer, "against baseline '{}'", report.baseline)?;
+                for variance in report.features.iter() {
+                    writeln!(
+                        writer,
+                        "  {} start {} finish {}",
+                        variance.feature_id,
+                        days(variance.start_variance),
+                        days(variance.finish_variance)
+                    )?;
+                }
+                for milestone in report.slipped_milestones.iter() {
+                    writeln!(
+                        writer,
+                        "slipped milestone {} {}",
+                        milestone.feature_id,
+                        days(milestone.finish_variance)
+                    )?;
+                }
+                for program in report.programs.iter() {
+                    writeln!(
+                        writer,
+                        "program {} finish {}",
+                        program.program_id,
+                        days(program.slip)
+                    )?;
+                }
+                writeln!(writer, "added: {}", report.added.join(", "))?;
+                writeln!(writer, "removed: {}", report.removed.join(", "))?;
+                writeln!(writer, "total slip {}", days(report.total_slip))
+            })?;
+        }
+
+        ScheduleReport::Simulation => {
+            let report = Simulation::new(schedule.iterations, schedule.seed).run(graph)?;
+            emit(report_args.format, &report_args.output, &report, |writer| {
+                for program in report.programs.iter() {
+                    writeln!(
+                        writer,
+                        "{} planned {} P50 {} P80 {} P95 {}",
+                        program.program_id,
+                        program.planned_finish.to_rfc3339(),
+                        program.p50.to_rfc3339(),
+                        program.p80.to_rfc3339(),
+                        program.p95.to_rfc3339()
+                    )?;
+                    for (feature_id, criticality) in program.criticality.iter() {
+                        writeln!(writer, "  {feature_id} criticality {criticality:.2}")?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_earned_value(
+    writer: &mut dyn Write,
+    feature: &FeatureEarnedValue,
+    depth: usize,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{}{} {}",
+        "  ".repeat(depth),
+        feature.feature_id,
+        figures(&feature.figures)
+    )?;
+    for subfeature in feature.subfeatures.iter() {
+        write_earned_value(writer, subfeature, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn figures(figures: &EarnedValue) -> String {
+    let index = |index: Option<f64>| match index {
+        Some(index) => format!("{index:.2}"),
+        None => "-".into(),
+    };
+    let actual_cost = match figures.actual_cost {
+        Some(actual_cost) => format!("{actual_cost:.2}"),
+        None => "-".into(),
+    };
+    format!(
+        "BAC {:.2} PV {:.2} EV {:.2} AC {} SPI {} CPI {}",
+        figures.budget_at_completion,
+        figures.planned_value,
+        figures.earned_value,
+        actual_cost,
+        index(figures.schedule_performance_index),
+        index(figures.cost_performance_index)
+    )
+}
+
+fn days(duration: Duration) -> String {
+    format!("{:+.1}d", duration.num_seconds() as f64 / 86_400.0)
+}
+
+/// A feature matching a query
+#[derive(Debug, Serialize)]
+struct QueryRow {
+    program_id: Symbol,
+    wbs: Option<WbsCode>,
+    #[serde(rename = "feature")]
+    feature_id: FeatureID,
+    progress_status: Symbol,
+    assigned_team: Symbol,
+    start_date: DateTime<FixedOffset>,
+    end_date: DateTime<FixedOffset>,
+    kind: FeatureKind,
+}
+
+fn query(graph: &ProgramGraph, filter: &QueryArgs) -> Vec<QueryRow> {
+    let mut rows: Vec<QueryRow> = graph
+        .programs
+        .iter()
+        .filter(|program| filter.program.as_ref().is_none_or(|id| &program.id == id))
+        .flat_map(|program| {
+            let features: Vec<&Feature> = match (&filter.feature, &filter.wbs) {
+                (None, None) => program.root.flatten(),
+                (feature_id, code) => program
+                    .root
+                    .flatten()
+                    .into_iter()
+                    .find(|feature| {
+                        feature_id.as_ref().is_none_or(|id| &feature.id == id)
+                            && code
+                                .as_ref()
+                                .is_none_or(|code| feature.wbs.as_ref() == Some(code))
+                    })
+                    .map(|feature| feature.flatten())
+                    .unwrap_or_default(),
+            };
+            features.into_iter().map(|feature| (&program.id, feature))
+        })
+        .filter(|(_, feature)| {
+            filter
+                .team
+                .as_ref()
+                .is_none_or(|team| &feature.assigned_team == team)
+                && filter
+                    .status
+                    .as_ref()
+                    .is_none_or(|status| &feature.progress_status == status)
+                && (!filter.milestones || feature.is_milestone())
+        })
+        .map(|(program_id, feature)| QueryRow {
+            program_id: program_id.clone(),
+            wbs: feature.wbs.clone(),
+            feature_id: feature.id.clone(),
+            progress_status: feature.progress_status.clone(),
+            assigned_team: feature.assigned_team.clone(),
+            start_date: feature.start_date,
+            end_date: feature.end_date,
+            kind: feature.kind,
+        })
+        .collect();
+    rows.sort_by(|a, b| (a.start_date, &a.feature_id).cmp(&(b.start_date, &b.feature_id)));
+    rows
+}
+
+/// Build the ingester based on the specified files, globs and directories, merged in order, or STDIN,
+/// and apply the events (up to the as-of time)
+fn read_features(input: &InputArgs) -> anyhow::Result<Vec<RawFeature>> {
+    let mut formats = Formats::default();
+    formats.register(IcsFormat {
+        from: input.expand_from,
+        until: input.expand_until,
+        ..IcsFormat::default()
+    });
+    let format = input
+        .input_format
+        .as_deref()
+        .and_then(|name| formats.by_name(name));
+    let ingester = match &input.inputs[..] {
+        [] => format
+            .unwrap_or(&LineFormat)
+            .ingest("stdin", Box::new(BufReader::new(io::stdin())))?,
+        inputs => formats.ingest_paths(format, inputs)?,
+    };
+    Ok(match input.as_of {
+        Some(as_of) => ingester.into_builder().as_of(as_of).into_features(),
+        None => ingester.into_features(),
+    })
+}
+
+fn read_graph(input: &InputArgs) -> anyhow::Result<ProgramGraph> {
+    let features = read_features(input)?;
+    Ok(ProgramGraph::from(features))
+}
+
+fn writer(output: &OutputArgs) -> anyhow::Result<Box<dyn Write>> {
+    Ok(match output.output.as_deref() {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("could not create {}", path.display())
+            })?))
+        }
+        None => Box::new(io::stdout().lock()),
+    })
+}
+
+/// Write a report in the requested format, using `text` for the human readable version
+///
+/// The serialised formats all go through serde, so they keep the start_date ordering of programs and features.
+fn emit<T: Serialize>(
+    format: Format,
+    output: &OutputArgs,
+    report: &T,
+    text: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> anyhow::Result<()> {
+    let mut writer = writer(output)?;
+    match format {
+        Format::Text => text(&mut writer)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut writer, report)?;
+            writeln!(writer)?;
+        }
+        Format::JsonCompact => {
+            serde_json::to_writer(&mut writer, report)?;
+            writeln!(writer)?;
+        }
+        Format::Yaml => serde_yaml::to_writer(&mut writer, report)?,
+        Format::Toml => {
+            let toml = toml::to_string_pretty(report).map_err(|error| {
+                UsageError(format!(
+                    "this output cannot be written as TOML, which needs a table at the top level: {error}"
+                ))
+            })?;
+            write!(writer, "{toml}")?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
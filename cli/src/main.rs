@@ -1,13 +1,303 @@
-use std::{
-    env,
-    fs::File,
-    io::{self, BufReader},
-};
+use std::{fmt::Display, io, path::PathBuf, process::ExitCode, str::FromStr};
 
-use program_ingester::{input::Ingester, output::ProgramGraph};
+use chrono::{DateTime, FixedOffset};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use program_ingester::{
+    errors::ProgramIngesterError, render::Renderers, source::Formats, wbs::WbsCode,
+};
 use tracing_subscriber::layer::SubscriberExt;
 
-fn main() -> anyhow::Result<()> {
+mod commands;
+mod watch;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  Success
+  1  Validation failed: the input was read, but has problems
+  2  The command line arguments are not valid (eg: a format that cannot hold the output)
+  3  The input could not be parsed
+  4  An I/O operation failed (eg: a file could not be read or written)";
+
+/// Ingest program features and emit hierarchical program graphs
+#[derive(Debug, Parser)]
+#[command(version, after_help = EXIT_CODES)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Draw the programs as a Gantt chart
+    Render {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// The format of the chart (terminal, svg, mermaid, html, csv).
+        /// When omitted, it is picked by the extension of the output file, and it is terminal for STDOUT
+        #[arg(short, long, value_parser = chart_format)]
+        format: Option<String>,
+
+        #[command(flatten)]
+        chart: ChartArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        #[command(flatten)]
+        watch: WatchArgs,
+    },
+
+    /// Check the input for problems
+    #[command(after_help = EXIT_CODES)]
+    Validate {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Convert the input into a program graph
+    Convert {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// The format of the output (text is the Rust Debug form)
+        #[arg(short, long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+
+        /// Include the file, line and byte offset each feature was read from
+        #[arg(long)]
+        provenance: bool,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Summarise the programs
+    Stats {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Show the changes between two versions of the input
+    Diff {
+        /// The older input file
+        old: PathBuf,
+
+        /// The newer input file
+        new: PathBuf,
+
+        /// The format of both inputs (line, csv, json), from their extensions when omitted
+        #[arg(long, value_parser = input_format)]
+        input_format: Option<String>,
+
+        #[command(flatten)]
+        report: ReportArgs,
+    },
+
+    /// List the features matching all of the given filters
+    Query {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        filter: QueryArgs,
+
+        #[command(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Analyse the schedule as of a status date
+    Schedule {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        schedule: ScheduleArgs,
+
+        #[command(flatten)]
+        report: ReportArgs,
+    },
+}
+
+#[derive(Debug, Args)]
+struct InputArgs {
+    /// The input files, globs (eg: 'plans/*.txt') or directories, merged in order, or STDIN when omitted
+    inputs: Vec<PathBuf>,
+
+    /// The format of the input (line, csv, json, ics, mspdi).
+    /// When omitted, it is picked by file extension, and it is the line format for STDIN and unknown extensions
+    #[arg(long, value_parser = input_format)]
+    input_format: Option<String>,
+
+    /// Look at the plan as it was at this time (RFC 3339), only applying the events up to it
+    #[arg(long, value_parser = DateTime::parse_from_rfc3339)]
+    as_of: Option<DateTime<FixedOffset>>,
+
+    /// Expand recurring calendar (ics) events from this time (RFC 3339), leaving out the occurrences that end before it
+    #[arg(long, value_parser = DateTime::parse_from_rfc3339)]
+    expand_from: Option<DateTime<FixedOffset>>,
+
+    /// Expand recurring calendar (ics) events until this time (RFC 3339), needed for events that repeat forever
+    #[arg(long, value_parser = DateTime::parse_from_rfc3339)]
+    expand_until: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Args)]
+struct ChartArgs {
+    /// The number of columns for the time scale (drawings use 8 pixels per column)
+    #[arg(long, default_value_t = 80)]
+    width: usize,
+
+    /// Start the time scale at this time (RFC 3339), leaving out the features that end before it
+    #[arg(long, value_parser = DateTime::parse_from_rfc3339)]
+    from: Option<DateTime<FixedOffset>>,
+
+    /// End the time scale at this time (RFC 3339), leaving out the features that start after it
+    #[arg(long, value_parser = DateTime::parse_from_rfc3339)]
+    until: Option<DateTime<FixedOffset>>,
+
+    /// Group the rows by program (with the feature hierarchy) or by team
+    #[arg(long, value_enum, default_value_t = GroupBy::Program)]
+    group_by: GroupBy,
+
+    /// Only draw the features assigned to this team
+    #[arg(long)]
+    team: Option<String>,
+
+    /// Only draw the features with this progress status
+    #[arg(long)]
+    status: Option<String>,
+
+    /// Only draw milestones
+    #[arg(long)]
+    milestones: bool,
+
+    /// Only draw the features without subfeatures, eg: the deliverables and milestones
+    #[arg(long)]
+    leaves: bool,
+
+    /// Put the WBS code (eg: 1.2.3) in front of each feature
+    #[arg(long)]
+    wbs: bool,
+}
+
+#[derive(Debug, Args)]
+struct OutputArgs {
+    /// Write to this file instead of STDOUT
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct WatchArgs {
+    /// Keep running, and render again whenever an input file changes (or one is added or removed)
+    #[arg(long, requires = "inputs")]
+    watch: bool,
+
+    /// How often to check the input files for changes, in milliseconds
+    #[arg(long, default_value_t = 500, requires = "watch")]
+    interval: u64,
+}
+
+#[derive(Debug, Args)]
+struct ReportArgs {
+    /// The format of the output
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+struct QueryArgs {
+    /// Only features in this program
+    #[arg(long)]
+    program: Option<String>,
+
+    /// Only features assigned to this team
+    #[arg(long)]
+    team: Option<String>,
+
+    /// Only features with this progress status
+    #[arg(long)]
+    status: Option<String>,
+
+    /// Only this feature and its subfeatures
+    #[arg(long)]
+    feature: Option<String>,
+
+    /// Only the feature with this WBS code (eg: 1.2) and its subfeatures
+    #[arg(long, value_parser = WbsCode::from_str)]
+    wbs: Option<WbsCode>,
+
+    /// Only milestones
+    #[arg(long)]
+    milestones: bool,
+}
+
+#[derive(Debug, Args)]
+struct ScheduleArgs {
+    /// The analysis to run
+    #[arg(long, value_enum, default_value_t = ScheduleReport::Health)]
+    report: ScheduleReport,
+
+    /// The baseline to compare against (JSON, eg: from `convert --format json`), for the variance report
+    #[arg(long, required_if_eq("report", "variance"))]
+    baseline: Option<PathBuf>,
+
+    /// The number of simulation iterations
+    #[arg(long, default_value_t = 10_000)]
+    iterations: usize,
+
+    /// The seed for the simulation's random numbers
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// The date (RFC 3339) the health and earned value are reported at, now when omitted
+    #[arg(long, value_parser = DateTime::parse_from_rfc3339)]
+    status_date: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Human readable text
+    Text,
+    /// Pretty printed JSON
+    Json,
+    /// JSON on a single line
+    JsonCompact,
+    /// YAML
+    Yaml,
+    /// TOML (only for outputs that are a table, like `convert`)
+    Toml,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GroupBy {
+    Program,
+    Team,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ScheduleReport {
+    /// Overdue, should-have-started and at-risk features, by team
+    Health,
+    /// Earned value figures (PV, EV, SPI, CPI)
+    EarnedValue,
+    /// Variance against a baseline
+    Variance,
+    /// Monte Carlo finish date forecast
+    Simulation,
+}
+
+fn main() -> ExitCode {
     // Create a stdout logging layer
     let logger = tracing_subscriber::fmt::layer().with_writer(io::stderr);
 
@@ -25,30 +315,73 @@ fn main() -> anyhow::Result<()> {
     // Initialize tracing
     tracing::subscriber::set_global_default(subscriber).expect("initialize tracing subscriber");
 
-    // Read CLI arguments
-    let args: Vec<String> = env::args().collect();
+    // Read CLI arguments (clap exits with code 2 when they are not valid)
+    let cli = Cli::parse();
 
-    // one day we can do if-let chains, like:
-    // if let arg_len = args.len() && arg_len > 2 {
-    // so that we can use the variable, as well as specify the condition for the if block
-    if args.len() > 2 {
-        tracing::warn!("additional arguments supplied and will be ignored");
+    match commands::run(cli.command) {
+        Ok(code) => code,
+        Err(err) => {
+            tracing::error!("{err:#}");
+            exit_code(&err)
+        }
     }
+}
 
-    // Build the ingester based on the specified file, or STDIN
-    let ingester = if let Some(path) = args.get(1) {
-        let reader = BufReader::new(File::open(path)?);
-        Ingester::try_from(reader)?
-    } else {
-        let reader = BufReader::new(io::stdin());
-        Ingester::try_from(reader)?
-    };
+/// Check that the input format is one of the registered [Formats]
+fn input_format(name: &str) -> Result<String, String> {
+    let formats = Formats::default();
+    match formats.by_name(name) {
+        Some(_) => Ok(name.to_string()),
+        None => Err(format!(
+            "the formats are: {}",
+            formats.names().collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
 
-    // Build the graph
-    let graph = ProgramGraph::from(ingester.features);
+/// Check that the chart format is one of the registered [Renderers]
+fn chart_format(name: &str) -> Result<String, String> {
+    let renderers = Renderers::default();
+    match renderers.by_name(name) {
+        Some(_) => Ok(name.to_string()),
+        None => Err(format!(
+            "the formats are: {}",
+            renderers.names().collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// A problem with the command line arguments that is only found while running the command
+#[derive(Debug)]
+struct UsageError(String);
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
-    // Output the graph
-    println!("{graph:#?}");
+impl std::error::Error for UsageError {}
 
-    Ok(())
+/// Tell usage problems and I/O errors apart from input that could not be parsed
+fn exit_code(err: &anyhow::Error) -> ExitCode {
+    if err.chain().any(|cause| cause.is::<UsageError>()) {
+        return ExitCode::from(2);
+    }
+
+    let io_failure = err.chain().any(|cause| {
+        cause.is::<io::Error>()
+            || match cause.downcast_ref::<ProgramIngesterError>() {
+                Some(ProgramIngesterError::IoError { .. }) => true,
+                Some(ProgramIngesterError::InvalidJson { source }) => source.is_io(),
+                Some(ProgramIngesterError::InvalidCsv { source }) => source.is_io_error(),
+                _ => false,
+            }
+    });
+
+    if io_failure {
+        ExitCode::from(4)
+    } else {
+        ExitCode::from(3)
+    }
 }
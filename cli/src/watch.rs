@@ -0,0 +1,55 @@
+use std::{fs, path::Path, path::PathBuf, thread, time::Duration, time::SystemTime};
+
+use program_ingester::input::expand_paths;
+
+/// Run `action` now, and again whenever the files of `inputs` change, checking every `interval`.
+///
+/// Globs and directories are expanded on every check, so files that are added or removed are noticed too.
+/// Errors from `action` (eg: input that cannot be parsed while it is being edited) are logged, and watching carries on.
+/// Inputs that cannot be found are logged on the first check and whenever they disappear, and `action` waits for them.
+pub fn poll(
+    inputs: &[PathBuf],
+    interval: Duration,
+    mut action: impl FnMut() -> anyhow::Result<()>,
+) -> ! {
+    let mut last_seen = None;
+
+    loop {
+        let current = Some(stamps(inputs));
+        if current != last_seen {
+            match &current {
+                Some(Err(err)) => tracing::error!("{err}"),
+                _ => match action() {
+                    Ok(()) => tracing::info!("rendered {} input(s)", inputs.len()),
+                    Err(err) => tracing::error!("{err:#}"),
+                },
+            }
+            last_seen = current;
+        }
+        thread::sleep(interval);
+    }
+}
+
+// the modification time and size tell us if a file was written to
+type Stamp = (SystemTime, u64);
+
+// the stamp of every file the inputs expand to, or why they cannot be expanded (eg: a missing file)
+fn stamps(inputs: &[PathBuf]) -> Result<Vec<(PathBuf, Option<Stamp>)>, String> {
+    let paths = expand_paths(inputs).map_err(|err| err.to_string())?;
+    paths
+        .into_iter()
+        .map(|path| {
+            let stamp = stamp(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+            Ok((path, stamp))
+        })
+        .collect()
+}
+
+// the stamp of a file, which is `None` when the file system does not keep modification times
+fn stamp(path: &Path) -> Result<Option<Stamp>, std::io::Error> {
+    let metadata = fs::metadata(path)?;
+    Ok(metadata
+        .modified()
+        .ok()
+        .map(|modified| (modified, metadata.len())))
+}
@@ -0,0 +1,45 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+// run the CLI with the input piped into STDIN
+fn run_with_stdin(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the CLI should start");
+    child
+        .stdin
+        .take()
+        .expect("STDIN is piped")
+        .write_all(input.as_bytes())
+        .expect("the input should be written");
+    child.wait_with_output().expect("the CLI should finish")
+}
+
+#[test]
+fn from_stdin() {
+    let input = "2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite\n\
+                 2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email\n";
+    let output = run_with_stdin(&["convert"], input);
+    assert!(output.status.success());
+
+    let graph: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("the output should be JSON");
+    let root = &graph["programs"][0]["root"];
+    assert_eq!(root["feature"], "ProductivitySuite");
+    assert_eq!(root["subfeatures"][0]["feature"], "Email");
+
+    // problems are located in STDIN
+    let output = run_with_stdin(&["convert"], "not a feature\n");
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("stdin:1: "));
+
+    // a list cannot be written as TOML, which is a usage problem rather than an input problem
+    let output = run_with_stdin(&["query", "--format", "toml"], input);
+    assert_eq!(output.status.code(), Some(2));
+}
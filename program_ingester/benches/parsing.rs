@@ -0,0 +1,50 @@
+//! Compare the owned parsing path (Ingester) with the borrowed one (FeatureRefs)
+//!
+//! Run with: cargo bench -p program_ingester --bench parsing
+use std::io::BufReader;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use program_ingester::{borrowed::FeatureRefs, input::Ingester};
+
+// a root per program, with 9 features under each
+fn input(lines: usize) -> String {
+    (0..lines)
+        .map(|line| {
+            let program = line / 10;
+            match line % 10 {
+                0 => format!("2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program{program} InProgress TeamA null->root{program}\n"),
+                feature => format!("2023-02-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program{program} Complete Team{feature} root{program}->feature{line} percent=50 cost=100\n"),
+            }
+        })
+        .collect()
+}
+
+fn parsing(c: &mut Criterion) {
+    let input = input(10_000);
+
+    let mut group = c.benchmark_group("parsing");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            let ingester =
+                Ingester::from_source("bench", BufReader::new(black_box(input.as_bytes())))
+                    .expect("bench input is valid");
+            ingester.features.len()
+        })
+    });
+
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            FeatureRefs::new("bench", black_box(&input))
+                .collect::<Result<Vec<_>, _>>()
+                .expect("bench input is valid")
+                .len()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parsing);
+criterion_main!(benches);
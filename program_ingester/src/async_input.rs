@@ -0,0 +1,116 @@
+//! Ingest features from async readers (eg: tokio sockets and files) as a [Stream], when the `async` feature is enabled.
+//!
+//! Lines are parsed exactly like [Ingester::from_source](crate::input::Ingester::from_source) does:
+//! they carry the same provenance, and errors are wrapped in [ProgramIngesterError::Located].
+//! Like the sync version, the stream stops after the first error.
+use futures_util::{future, stream, Stream, StreamExt};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::{
+    errors::ProgramIngesterError,
+    input::{Line, Provenance, RawFeature},
+    symbol::Interner,
+};
+
+// what the stream needs to carry from one line to the next
+struct State<R> {
+    reader: R,
+    name: String,
+    buf: String,
+    line: usize,
+    offset: usize,
+    interner: Interner,
+    failed: bool,
+}
+
+/// Every line of the reader, features and events
+pub fn lines<R: AsyncBufRead + Unpin>(
+    name: impl Into<String>,
+    reader: R,
+) -> impl Stream<Item = Result<Line, ProgramIngesterError>> {
+    let state = State {
+        reader,
+        name: name.into(),
+        buf: String::new(),
+        line: 0,
+        offset: 0,
+        interner: Interner::new(),
+        failed: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.failed {
+            return None;
+        }
+
+        state.buf.clear();
+        let parsed = match state.reader.read_line(&mut state.buf).await {
+            Ok(0) => return None,
+            Ok(read) => {
+                state.line += 1;
+                let provenance = Provenance {
+                    source: state.name.clone(),
+                    line: state.line,
+                    offset: state.offset,
+                };
+                state.offset += read;
+                // remove the trailing \n
+                Line::parse_with(state.buf.trim_end(), provenance, &mut state.interner)
+            }
+            Err(error) => Err(error.into()),
+        };
+
+        state.failed = parsed.is_err();
+        Some((parsed, state))
+    })
+}
+
+/// The features of the reader, like [Ingester::features](crate::input::Ingester::features).
+///
+/// Event lines are skipped, use [lines] to receive them too.
+pub fn features<R: AsyncBufRead + Unpin>(
+    name: impl Into<String>,
+    reader: R,
+) -> impl Stream<Item = Result<RawFeature, ProgramIngesterError>> {
+    lines(name, reader).filter_map(|line| {
+        future::ready(match line {
+            Ok(Line::Feature(feature)) => Some(Ok(feature)),
+            Ok(Line::Event(_)) => None,
+            Err(error) => Some(Err(error)),
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use futures_util::StreamExt;
+    use indoc::indoc;
+    use std::io::BufReader;
+
+    use super::features;
+    use crate::input::Ingester;
+
+    #[tokio::test]
+    async fn test_same_as_sync_ingester() {
+        let input = indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            @2023-03-01T00:00:00Z update Email status=Complete
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email
+        "};
+
+        let streamed: Vec<_> = features("plan.txt", input.as_bytes())
+            .map(|feature| feature.expect("test input should be checked"))
+            .collect()
+            .await;
+        let ingester = Ingester::from_source("plan.txt", BufReader::new(input.as_bytes()))
+            .expect("test input should be checked");
+        assert_eq!(streamed, ingester.features);
+
+        // the stream ends with the first error, which names the line
+        let broken = format!("{input}not a feature\n{input}");
+        let results: Vec<_> = features("plan.txt", broken.as_bytes()).collect().await;
+        assert_eq!(results.len(), 3);
+        let error = results[2].as_ref().expect_err("line 4 is not valid");
+        assert!(error.to_string().starts_with("plan.txt:4: "));
+    }
+}
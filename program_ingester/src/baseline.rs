@@ -0,0 +1,289 @@
+//! Baselines are named snapshots of a [ProgramGraph] that are saved (as JSON) and later compared against the current plan.
+//! Features are matched to their baseline counterparts by ID, and the comparison reports how far each feature's start and finish
+//! have moved, which milestones have slipped, and how far each program's finish has slipped.
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{
+    errors::ProgramIngesterError,
+    input::{FeatureID, FeatureKind},
+    output::{Feature, Program, ProgramGraph},
+    symbol::Symbol,
+};
+
+/// A named snapshot of a [ProgramGraph].
+///
+/// It is stored in the same JSON format as the [ProgramGraph] output, with an additional `name` field.
+/// The name is optional when loading, so any saved [ProgramGraph] JSON can be used as a baseline.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Baseline {
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub graph: ProgramGraph,
+}
+
+impl Baseline {
+    pub fn new(name: impl Into<String>, graph: &ProgramGraph) -> Self {
+        Baseline {
+            name: name.into(),
+            graph: graph.clone(),
+        }
+    }
+
+    /// Write the baseline as JSON
+    pub fn save<W: Write>(&self, writer: W) -> Result<(), ProgramIngesterError> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Read a baseline previously written by [Baseline::save]
+    pub fn load<R: Read>(reader: R) -> Result<Self, ProgramIngesterError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Compare the current plan against this baseline
+    pub fn compare(&self, current: &ProgramGraph) -> VarianceReport {
+        let planned = features_by_id(&self.graph);
+        let actual = features_by_id(current);
+
+        let mut features: Vec<FeatureVariance> = actual
+            .iter()
+            .filter_map(|(feature_id, (program, feature))| {
+                planned
+                    .get(feature_id)
+                    .map(|(_, baseline)| FeatureVariance {
+                        program_id: program.id.clone(),
+                        feature_id: feature_id.clone(),
+                        kind: feature.kind,
+                        start_variance: feature.start_date - baseline.start_date,
+                        finish_variance: feature.end_date - baseline.end_date,
+                    })
+            })
+            .collect();
+        features.sort_by_key(|variance| {
+            let (_, feature) = actual[&variance.feature_id];
+            (feature.start_date, variance.feature_id.clone())
+        });
+
+        let slipped_milestones = features
+            .iter()
+            .filter(|variance| variance.kind == FeatureKind::Milestone && variance.has_slipped())
+            .cloned()
+            .collect();
+
+        let mut added: Vec<FeatureID> = actual
+            .keys()
+            .filter(|&feature_id| !planned.contains_key(feature_id))
+            .cloned()
+            .collect();
+        added.sort();
+
+        let mut removed: Vec<FeatureID> = planned
+            .keys()
+            .filter(|&feature_id| !actual.contains_key(feature_id))
+            .cloned()
+            .collect();
+        removed.sort();
+
+        let mut programs: Vec<ProgramSlip> = current
+            .programs
+            .iter()
+            .filter_map(|program| {
+                self.graph
+                    .programs
+                    .iter()
+                    .find(|baseline| baseline.id == program.id)
+                    .map(|baseline| {
+                        let baseline_finish = program_finish(baseline);
+                        let current_finish = program_finish(program);
+                        ProgramSlip {
+                            program_id: program.id.clone(),
+                            baseline_finish,
+                            current_finish,
+                            slip: current_finish - baseline_finish,
+                        }
+                    })
+            })
+            .collect();
+        programs.sort_by(|a, b| a.program_id.cmp(&b.program_id));
+
+        let total_slip = match (graph_finish(current), graph_finish(&self.graph)) {
+            (Some(current_finish), Some(baseline_finish)) => current_finish - baseline_finish,
+            _ => Duration::zero(),
+        };
+
+        VarianceReport {
+            baseline: self.name.clone(),
+            features,
+            slipped_milestones,
+            programs,
+            added,
+            removed,
+            total_slip,
+        }
+    }
+}
+
+/// How far a single feature has moved compared to the baseline.
+///
+/// Positive variances mean the feature is later than planned. They are serialised as (fractional) days.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FeatureVariance {
+    pub program_id: Symbol,
+    #[serde(rename = "feature")]
+    pub feature_id: FeatureID,
+    pub kind: FeatureKind,
+    #[serde(serialize_with = "as_days")]
+    pub start_variance: Duration,
+    #[serde(serialize_with = "as_days")]
+    pub finish_variance: Duration,
+}
+
+impl FeatureVariance {
+    pub fn has_slipped(&self) -> bool {
+        self.finish_variance > Duration::zero()
+    }
+}
+
+/// How far a program's finish (the latest end_date of any of its features) has moved compared to the baseline
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ProgramSlip {
+    pub program_id: Symbol,
+    pub baseline_finish: DateTime<FixedOffset>,
+    pub current_finish: DateTime<FixedOffset>,
+    #[serde(serialize_with = "as_days")]
+    pub slip: Duration,
+}
+
+/// The result of comparing a [ProgramGraph] against a [Baseline]
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct VarianceReport {
+    /// The name of the baseline that was compared against
+    pub baseline: String,
+
+    /// Variances of the features found in both the baseline and the current plan, ordered by current start_date
+    pub features: Vec<FeatureVariance>,
+
+    /// Milestones whose finish is later than in the baseline
+    pub slipped_milestones: Vec<FeatureVariance>,
+
+    /// Finish slip per program found in both the baseline and the current plan
+    pub programs: Vec<ProgramSlip>,
+
+    /// Feature IDs that are not in the baseline
+    pub added: Vec<FeatureID>,
+
+    /// Feature IDs that are no longer in the current plan
+    pub removed: Vec<FeatureID>,
+
+    /// How far the finish of the whole plan has slipped
+    #[serde(serialize_with = "as_days")]
+    pub total_slip: Duration,
+}
+
+// index every feature in the graph by its ID, along with the program it belongs to
+fn features_by_id(graph: &ProgramGraph) -> HashMap<FeatureID, (&Program, &Feature)> {
+    graph
+        .programs
+        .iter()
+        .flat_map(|program| {
+            program
+                .root
+                .flatten()
+                .into_iter()
+                .map(move |feature| (feature.id.clone(), (program, feature)))
+        })
+        .collect()
+}
+
+fn program_finish(program: &Program) -> DateTime<FixedOffset> {
+    program
+        .root
+        .flatten()
+        .iter()
+        .map(|feature| feature.end_date)
+        .max()
+        .expect("a program always has a root feature")
+}
+
+fn graph_finish(graph: &ProgramGraph) -> Option<DateTime<FixedOffset>> {
+    graph.programs.iter().map(program_finish).max()
+}
+
+/// Custom serializer for [Duration], as a number of days
+fn as_days<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_f64(value.num_seconds() as f64 / 86_400.0)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use indoc::indoc;
+
+    use super::Baseline;
+    use crate::testing::graph;
+
+    #[test]
+    fn test_variance_against_baseline() {
+        let planned = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email
+            2023-06-30T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->EmailRelease
+            2023-02-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Chat
+        "});
+        let current = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2024-01-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-01-11T00:00:00.000Z 2023-07-20T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email
+            2023-07-20T00:00:00.000Z 2023-07-20T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->EmailRelease
+            2023-03-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Calendar
+        "});
+
+        let baseline = Baseline::new("kickoff", &planned);
+        let report = baseline.compare(&current);
+
+        assert_eq!(report.baseline, "kickoff");
+        assert_eq!(report.features.len(), 3);
+
+        let email = report
+            .features
+            .iter()
+            .find(|variance| variance.feature_id == "Email")
+            .expect("Email is in both plans");
+        assert_eq!(email.start_variance, Duration::days(10));
+        assert_eq!(email.finish_variance, Duration::days(20));
+
+        assert_eq!(report.slipped_milestones.len(), 1);
+        assert_eq!(report.slipped_milestones[0].feature_id, "EmailRelease");
+
+        assert_eq!(report.added, vec!["Calendar".to_string()]);
+        assert_eq!(report.removed, vec!["Chat".to_string()]);
+
+        assert_eq!(report.programs.len(), 1);
+        assert_eq!(report.programs[0].slip, Duration::days(31));
+        assert_eq!(report.total_slip, Duration::days(31));
+    }
+
+    #[test]
+    fn test_baseline_json_roundtrip() {
+        let planned = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-06-30T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Release
+        "});
+        let baseline = Baseline::new("kickoff", &planned);
+
+        let mut json = vec![];
+        baseline.save(&mut json).expect("baseline should serialise");
+        let loaded = Baseline::load(json.as_slice()).expect("baseline should deserialise");
+
+        assert_eq!(loaded, baseline);
+    }
+}
@@ -0,0 +1,253 @@
+//! Parse feature lines without copying them: [RawFeatureRef] borrows its text fields from the line,
+//! so a whole buffer (eg: a file that was read, or memory-mapped) can be parsed without an allocation per field.
+//! Convert to the owned [RawFeature] with [RawFeatureRef::into_owned], only for the features that need it.
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    errors::ProgramIngesterError,
+    events::EVENT_MARKER,
+    input::{
+        parse_cost, parse_percent, FeatureKind, Provenance, RawFeature, ThreePointEstimate,
+        MILESTONE_MARKER,
+    },
+    symbol::Interner,
+};
+
+/// A [RawFeature] that borrows its text from the line it was parsed from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawFeatureRef<'a> {
+    pub id: &'a str,
+    pub parent_id: Option<&'a str>,
+    pub program_id: &'a str,
+    pub progress_status: &'a str,
+    pub assigned_team: &'a str,
+    pub start_date: DateTime<FixedOffset>,
+    pub end_date: DateTime<FixedOffset>,
+    pub kind: FeatureKind,
+    pub percent_complete: Option<u8>,
+    pub planned_cost: Option<f64>,
+    pub actual_cost: Option<f64>,
+    pub estimate: Option<ThreePointEstimate>,
+}
+
+impl<'a> RawFeatureRef<'a> {
+    /// Parse a feature line, see [RawFeature::from_str] for the format
+    pub fn parse(s: &'a str) -> Result<Self, ProgramIngesterError> {
+        let mut parts = s.trim().split(' ');
+        // We should have at least 6 parts.
+        let (
+            Some(start),
+            Some(end),
+            Some(program_id),
+            Some(progress_status),
+            Some(assigned_team),
+            Some(relation_part),
+        ) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        )
+        else {
+            return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                "The feature '{s}' needs to have 6 parts, start, end, program, progress_status, assigned_team, feature-relation"
+            )));
+        };
+
+        // If so, then we need to split the last required part
+        let (parent_id, id) = match relation_part.split_once("->") {
+            Some((parent_id, id)) if !id.contains("->") => (parent_id, id),
+            _ => {
+                return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                    "The feature-relation '{s}' needs to have 2 parts"
+                )))
+            }
+        };
+
+        let start_date = DateTime::parse_from_rfc3339(start)?;
+        let end_date = DateTime::parse_from_rfc3339(end)?;
+
+        // Zero length features are milestones, even without the marker
+        let mut kind = if start_date == end_date {
+            FeatureKind::Milestone
+        } else {
+            FeatureKind::Task
+        };
+        let mut percent_complete = None;
+        let mut planned_cost = None;
+        let mut actual_cost = None;
+        let mut estimate = None;
+
+        for attribute in parts {
+            match attribute.split_once('=') {
+                None if attribute == MILESTONE_MARKER => kind = FeatureKind::Milestone,
+                Some(("percent", value)) => percent_complete = Some(parse_percent(value, s)?),
+                Some(("cost", value)) => planned_cost = Some(parse_cost(value, s)?),
+                Some(("actual", value)) => actual_cost = Some(parse_cost(value, s)?),
+                Some(("estimate", value)) => estimate = Some(ThreePointEstimate::from_str(value)?),
+                _ => {
+                    return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                        "The attribute '{attribute}' in '{s}' is not one of '{MILESTONE_MARKER}', 'percent=', 'cost=', 'actual=', 'estimate='"
+                    )))
+                }
+            }
+        }
+
+        Ok(RawFeatureRef {
+            id,
+            parent_id: match parent_id {
+                "null" => None,
+                id => Some(id),
+            },
+            program_id,
+            progress_status,
+            assigned_team,
+            start_date,
+            end_date,
+            kind,
+            percent_complete,
+            planned_cost,
+            actual_cost,
+            estimate,
+        })
+    }
+
+    /// Copy the text fields into an owned [RawFeature], without provenance
+    pub fn into_owned(self) -> RawFeature {
+        self.intern(&mut Interner::new())
+    }
+
+    /// Like [RawFeatureRef::into_owned], sharing the text fields with the other features of the interner
+    pub fn intern(self, interner: &mut Interner) -> RawFeature {
+        RawFeature {
+            id: interner.intern(self.id),
+            parent_id: self.parent_id.map(|parent_id| interner.intern(parent_id)),
+            program_id: interner.intern(self.program_id),
+            progress_status: interner.intern(self.progress_status),
+            assigned_team: interner.intern(self.assigned_team),
+            start_date: self.start_date,
+            end_date: self.end_date,
+            kind: self.kind,
+            percent_complete: self.percent_complete,
+            planned_cost: self.planned_cost,
+            actual_cost: self.actual_cost,
+            estimate: self.estimate,
+            provenance: None,
+        }
+    }
+}
+
+/// The features of a buffer, in order. Event lines are skipped (see [crate::events]).
+///
+/// Like [Ingester::from_source](crate::input::Ingester::from_source), errors are wrapped in
+/// [ProgramIngesterError::Located], and the iterator stops after the first error.
+pub struct FeatureRefs<'a> {
+    name: &'a str,
+    rest: &'a str,
+    line: usize,
+    offset: usize,
+    // where the line that was last returned starts
+    last_offset: usize,
+    failed: bool,
+}
+
+impl<'a> FeatureRefs<'a> {
+    pub fn new(name: &'a str, buffer: &'a str) -> Self {
+        FeatureRefs {
+            name,
+            rest: buffer,
+            line: 0,
+            offset: 0,
+            last_offset: 0,
+            failed: false,
+        }
+    }
+
+    /// Check that the bytes (eg: of a memory-mapped file) are UTF-8, without copying them
+    pub fn from_bytes(name: &'a str, buffer: &'a [u8]) -> Result<Self, ProgramIngesterError> {
+        let buffer = std::str::from_utf8(buffer).map_err(|error| {
+            ProgramIngesterError::InvalidProgramInput(format!("{name} is not UTF-8: {error}"))
+        })?;
+        Ok(FeatureRefs::new(name, buffer))
+    }
+
+    /// Where the feature that was last returned was read from
+    pub fn provenance(&self) -> Provenance {
+        Provenance {
+            source: self.name.into(),
+            line: self.line,
+            offset: self.last_offset,
+        }
+    }
+}
+
+impl<'a> Iterator for FeatureRefs<'a> {
+    type Item = Result<RawFeatureRef<'a>, ProgramIngesterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed && !self.rest.is_empty() {
+            let (text, rest) = match self.rest.find('\n') {
+                Some(end) => (&self.rest[..end], &self.rest[end + 1..]),
+                None => (self.rest, ""),
+            };
+            self.line += 1;
+            self.last_offset = self.offset;
+            self.offset += self.rest.len() - rest.len();
+            self.rest = rest;
+
+            // remove the trailing \r (the \n is already gone)
+            let text = text.trim_end();
+            if text.starts_with(EVENT_MARKER) {
+                continue;
+            }
+
+            let parsed =
+                RawFeatureRef::parse(text).map_err(|error| ProgramIngesterError::Located {
+                    location: format!("{}:{}", self.name, self.line),
+                    error: Box::new(error),
+                });
+            self.failed = parsed.is_err();
+            return Some(parsed);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use std::io::BufReader;
+
+    use super::FeatureRefs;
+    use crate::input::Ingester;
+
+    #[test]
+    fn test_same_as_owned_parsing() {
+        let input = indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            @2023-03-01T00:00:00Z update Email status=Complete
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email percent=100 cost=10
+        "};
+        let ingester = Ingester::from_source("plan.txt", BufReader::new(input.as_bytes()))
+            .expect("test input should be checked");
+
+        let mut refs = FeatureRefs::new("plan.txt", input);
+        let mut owned = vec![];
+        while let Some(feature) = refs.next() {
+            let mut feature = feature.expect("test input should be checked").into_owned();
+            feature.provenance = Some(refs.provenance());
+            owned.push(feature);
+        }
+        assert_eq!(owned, ingester.features);
+
+        let broken = format!("{input}not a feature\n{input}");
+        let results: Vec<_> = FeatureRefs::new("plan.txt", &broken).collect();
+        assert_eq!(results.len(), 3);
+        let error = results[2].as_ref().expect_err("line 4 is not valid");
+        assert!(error.to_string().starts_with("plan.txt:4: "));
+    }
+}
@@ -0,0 +1,374 @@
+//! Compare two snapshots of a [ProgramGraph] and report what changed between them.
+//!
+//! Features are matched by ID. Each difference is a structured [Change] record, which can be serialised (eg: as JSON),
+//! or printed as a human-readable changelog through the [Display] implementation of [Changeset].
+//! Changes cite where the feature was read from, when it is known: the older version for removals, otherwise the newer one.
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+
+use crate::{
+    input::{FeatureID, Provenance},
+    output::{Feature, ProgramGraph},
+    symbol::Symbol,
+};
+
+/// Types that can report the differences between two versions of themselves
+pub trait Delta {
+    type Output;
+
+    /// What changed going from `self` to `newer`
+    fn delta(&self, newer: &Self) -> Self::Output;
+}
+
+/// A single difference between two [ProgramGraph]s
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Added {
+        program_id: Symbol,
+        #[serde(rename = "feature")]
+        feature_id: FeatureID,
+        parent_id: Option<FeatureID>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+    },
+    Removed {
+        program_id: Symbol,
+        #[serde(rename = "feature")]
+        feature_id: FeatureID,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+    },
+    /// The feature was given a different parent
+    Moved {
+        #[serde(rename = "feature")]
+        feature_id: FeatureID,
+        from: Option<FeatureID>,
+        to: Option<FeatureID>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+    },
+    Rescheduled {
+        #[serde(rename = "feature")]
+        feature_id: FeatureID,
+        from_start: DateTime<FixedOffset>,
+        from_end: DateTime<FixedOffset>,
+        to_start: DateTime<FixedOffset>,
+        to_end: DateTime<FixedOffset>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+    },
+    Reassigned {
+        #[serde(rename = "feature")]
+        feature_id: FeatureID,
+        from: Symbol,
+        to: Symbol,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+    },
+    StatusChanged {
+        #[serde(rename = "feature")]
+        feature_id: FeatureID,
+        from: Symbol,
+        to: Symbol,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+    },
+}
+
+impl Change {
+    pub fn feature_id(&self) -> &FeatureID {
+        match self {
+            Change::Added { feature_id, .. }
+            | Change::Removed { feature_id, .. }
+            | Change::Moved { feature_id, .. }
+            | Change::Rescheduled { feature_id, .. }
+            | Change::Reassigned { feature_id, .. }
+            | Change::StatusChanged { feature_id, .. } => feature_id,
+        }
+    }
+
+    pub fn provenance(&self) -> Option<&Provenance> {
+        match self {
+            Change::Added { provenance, .. }
+            | Change::Removed { provenance, .. }
+            | Change::Moved { provenance, .. }
+            | Change::Rescheduled { provenance, .. }
+            | Change::Reassigned { provenance, .. }
+            | Change::StatusChanged { provenance, .. } => provenance.as_ref(),
+        }
+    }
+}
+
+/// Print a change as a line of a changelog
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn parent(parent_id: &Option<FeatureID>) -> &str {
+            parent_id.as_deref().unwrap_or("(root)")
+        }
+
+        match self {
+            Change::Added {
+                program_id,
+                feature_id,
+                parent_id,
+                ..
+            } => write!(
+                f,
+                "+ {feature_id}: added under {} in {program_id}",
+                parent(parent_id)
+            ),
+            Change::Removed {
+                program_id,
+                feature_id,
+                ..
+            } => write!(f, "- {feature_id}: removed from {program_id}"),
+            Change::Moved {
+                feature_id,
+                from,
+                to,
+                ..
+            } => write!(
+                f,
+                "~ {feature_id}: moved from {} to {}",
+                parent(from),
+                parent(to)
+            ),
+            Change::Rescheduled {
+                feature_id,
+                from_start,
+                from_end,
+                to_start,
+                to_end,
+                ..
+            } => write!(
+                f,
+                "~ {feature_id}: rescheduled from {} - {} to {} - {}",
+                from_start.to_rfc3339(),
+                from_end.to_rfc3339(),
+                to_start.to_rfc3339(),
+                to_end.to_rfc3339()
+            ),
+            Change::Reassigned {
+                feature_id,
+                from,
+                to,
+                ..
+            } => write!(f, "~ {feature_id}: reassigned from {from} to {to}"),
+            Change::StatusChanged {
+                feature_id,
+                from,
+                to,
+                ..
+            } => write!(f, "~ {feature_id}: status changed from {from} to {to}"),
+        }?;
+        match self.provenance() {
+            Some(provenance) => write!(f, " (at {provenance})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// All of the changes between two [ProgramGraph]s, ordered by feature ID
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+pub struct Changeset {
+    pub changes: Vec<Change>,
+}
+
+impl Changeset {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Print the changeset as a changelog, one change per line
+impl Display for Changeset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in self.changes.iter() {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+// Where a feature sits in the graph
+struct Placement<'a> {
+    program_id: &'a Symbol,
+    parent_id: Option<&'a FeatureID>,
+    feature: &'a Feature,
+}
+
+// index every feature in the graph by its ID, along with its program and parent
+fn placements(graph: &ProgramGraph) -> HashMap<&FeatureID, Placement<'_>> {
+    fn place<'a>(
+        program_id: &'a Symbol,
+        parent_id: Option<&'a FeatureID>,
+        feature: &'a Feature,
+        placements: &mut HashMap<&'a FeatureID, Placement<'a>>,
+    ) {
+        placements.insert(
+            &feature.id,
+            Placement {
+                program_id,
+                parent_id,
+                feature,
+            },
+        );
+        for subfeature in feature.subfeatures.iter() {
+            place(program_id, Some(&feature.id), subfeature, placements);
+        }
+    }
+
+    let mut placements = HashMap::new();
+    for program in graph.programs.iter() {
+        place(&program.id, None, &program.root, &mut placements);
+    }
+    placements
+}
+
+impl Delta for ProgramGraph {
+    type Output = Changeset;
+
+    fn delta(&self, newer: &Self) -> Changeset {
+        let before = placements(self);
+        let after = placements(newer);
+
+        let mut changes = vec![];
+
+        for (feature_id, old) in before.iter() {
+            let Some(new) = after.get(feature_id) else {
+                changes.push(Change::Removed {
+                    program_id: old.program_id.clone(),
+                    feature_id: (*feature_id).clone(),
+                    provenance: old.feature.provenance.clone(),
+                });
+                continue;
+            };
+
+            if old.parent_id != new.parent_id {
+                changes.push(Change::Moved {
+                    feature_id: (*feature_id).clone(),
+                    from: old.parent_id.cloned(),
+                    to: new.parent_id.cloned(),
+                    provenance: new.feature.provenance.clone(),
+                });
+            }
+
+            if old.feature.start_date != new.feature.start_date
+                || old.feature.end_date != new.feature.end_date
+            {
+                changes.push(Change::Rescheduled {
+                    feature_id: (*feature_id).clone(),
+                    from_start: old.feature.start_date,
+                    from_end: old.feature.end_date,
+                    to_start: new.feature.start_date,
+                    to_end: new.feature.end_date,
+                    provenance: new.feature.provenance.clone(),
+                });
+            }
+
+            if old.feature.assigned_team != new.feature.assigned_team {
+                changes.push(Change::Reassigned {
+                    feature_id: (*feature_id).clone(),
+                    from: old.feature.assigned_team.clone(),
+                    to: new.feature.assigned_team.clone(),
+                    provenance: new.feature.provenance.clone(),
+                });
+            }
+
+            if old.feature.progress_status != new.feature.progress_status {
+                changes.push(Change::StatusChanged {
+                    feature_id: (*feature_id).clone(),
+                    from: old.feature.progress_status.clone(),
+                    to: new.feature.progress_status.clone(),
+                    provenance: new.feature.provenance.clone(),
+                });
+            }
+        }
+
+        for (feature_id, new) in after.iter() {
+            if !before.contains_key(feature_id) {
+                changes.push(Change::Added {
+                    program_id: new.program_id.clone(),
+                    feature_id: (*feature_id).clone(),
+                    parent_id: new.parent_id.cloned(),
+                    provenance: new.feature.provenance.clone(),
+                });
+            }
+        }
+
+        // the maps have no order, so sort by feature ID (stable, so each feature's changes keep the order above)
+        changes.sort_by(|a, b| a.feature_id().cmp(b.feature_id()));
+
+        Changeset { changes }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use super::{Change, Delta};
+    use crate::testing::graph;
+
+    #[test]
+    fn test_delta_between_snapshots() {
+        let before = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email
+            2023-01-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->EmailSearch
+            2023-02-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Chat
+        "});
+        let after = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-01-01T00:00:00.000Z 2023-07-31T00:00:00.000Z program1 Complete TeamD ProductivitySuite->Email
+            2023-01-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 InProgress TeamB Email->EmailSearch
+            2023-03-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Calendar
+        "});
+
+        let changeset = before.delta(&after);
+
+        let kinds: Vec<(&str, &str)> = changeset
+            .changes
+            .iter()
+            .map(|change| {
+                let kind = match change {
+                    Change::Added { .. } => "added",
+                    Change::Removed { .. } => "removed",
+                    Change::Moved { .. } => "moved",
+                    Change::Rescheduled { .. } => "rescheduled",
+                    Change::Reassigned { .. } => "reassigned",
+                    Change::StatusChanged { .. } => "status_changed",
+                };
+                (change.feature_id().as_str(), kind)
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Calendar", "added"),
+                ("Chat", "removed"),
+                ("Email", "rescheduled"),
+                ("Email", "reassigned"),
+                ("Email", "status_changed"),
+                ("EmailSearch", "moved"),
+            ]
+        );
+
+        let changelog = changeset.to_string();
+        assert!(changelog
+            .contains("+ Calendar: added under ProductivitySuite in program1 (at input:4)\n"));
+        assert!(changelog.contains("- Chat: removed from program1 (at input:4)\n"));
+        assert!(changelog
+            .contains("~ EmailSearch: moved from ProductivitySuite to Email (at input:3)\n"));
+
+        let json = serde_json::to_value(&changeset).expect("changes should serialise");
+        assert_eq!(json["changes"][0]["change"], "added");
+        assert_eq!(json["changes"][0]["feature"], "Calendar");
+        assert_eq!(json["changes"][0]["provenance"]["line"], 4);
+
+        assert!(after.delta(&after).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! Earned value figures for the features of a [ProgramGraph], as of a status date.
+//!
+//! For each feature with a planned_cost:
+//! - Planned Value (PV) is the planned_cost scaled by how much of the feature's time has elapsed at the status date
+//! - Earned Value (EV) is the planned_cost scaled by the feature's progress (see [Feature::progress])
+//! - Actual Cost (AC) is the actual_cost, when it is given
+//!
+//! A feature's figures include the figures of all of its subfeatures, so the root feature's figures are the program's.
+//! From these, the Schedule Performance Index (SPI = EV / PV) and the Cost Performance Index (CPI = EV / AC) are derived.
+//! CPI only compares the EV of the features that have an actual_cost, so features without actuals do not look under budget.
+use std::ops::Add;
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+
+use crate::{
+    input::FeatureID,
+    output::{Feature, Program, ProgramGraph},
+    symbol::Symbol,
+};
+
+/// Earned value figures for a feature, or a rollup of features
+#[derive(Debug, Default, Serialize, Clone, Copy, PartialEq)]
+pub struct EarnedValue {
+    /// The total planned cost (BAC)
+    pub budget_at_completion: f64,
+    pub planned_value: f64,
+    pub earned_value: f64,
+    pub actual_cost: Option<f64>,
+    /// The EV of the features with an actual_cost, which CPI is computed from
+    #[serde(skip)]
+    pub costed_earned_value: f64,
+    pub schedule_performance_index: Option<f64>,
+    pub cost_performance_index: Option<f64>,
+}
+
+impl EarnedValue {
+    /// The figures of a single feature
+    pub fn new(
+        budget_at_completion: f64,
+        planned_value: f64,
+        earned_value: f64,
+        actual_cost: Option<f64>,
+    ) -> Self {
+        let costed_earned_value = match actual_cost {
+            Some(_) => earned_value,
+            None => 0.0,
+        };
+        EarnedValue::with_indexes(
+            budget_at_completion,
+            planned_value,
+            earned_value,
+            actual_cost,
+            costed_earned_value,
+        )
+    }
+
+    fn with_indexes(
+        budget_at_completion: f64,
+        planned_value: f64,
+        earned_value: f64,
+        actual_cost: Option<f64>,
+        costed_earned_value: f64,
+    ) -> Self {
+        EarnedValue {
+            budget_at_completion,
+            planned_value,
+            earned_value,
+            actual_cost,
+            costed_earned_value,
+            schedule_performance_index: ratio(earned_value, Some(planned_value)),
+            cost_performance_index: ratio(costed_earned_value, actual_cost),
+        }
+    }
+}
+
+// indexes are undefined when there is nothing to divide by
+fn ratio(numerator: f64, denominator: Option<f64>) -> Option<f64> {
+    denominator
+        .filter(|&denominator| denominator > 0.0)
+        .map(|denominator| numerator / denominator)
+}
+
+/// Roll up figures, recomputing the indexes from the totals
+impl Add for EarnedValue {
+    type Output = EarnedValue;
+
+    fn add(self, other: EarnedValue) -> EarnedValue {
+        let actual_cost = match (self.actual_cost, other.actual_cost) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or_default() + b.unwrap_or_default()),
+        };
+        EarnedValue::with_indexes(
+            self.budget_at_completion + other.budget_at_completion,
+            self.planned_value + other.planned_value,
+            self.earned_value + other.earned_value,
+            actual_cost,
+            self.costed_earned_value + other.costed_earned_value,
+        )
+    }
+}
+
+/// Earned value figures of a feature (including its subfeatures)
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FeatureEarnedValue {
+    #[serde(rename = "feature")]
+    pub feature_id: FeatureID,
+    #[serde(flatten)]
+    pub figures: EarnedValue,
+    pub subfeatures: Vec<FeatureEarnedValue>,
+}
+
+impl FeatureEarnedValue {
+    pub fn as_of(feature: &Feature, status_date: DateTime<FixedOffset>) -> Self {
+        let own = match feature.planned_cost {
+            Some(planned_cost) => EarnedValue::new(
+                planned_cost,
+                planned_cost * planned_progress(feature, status_date),
+                planned_cost * feature.progress(),
+                feature.actual_cost,
+            ),
+            None => EarnedValue::new(0.0, 0.0, 0.0, feature.actual_cost),
+        };
+
+        let mut subfeatures: Vec<FeatureEarnedValue> = feature
+            .subfeatures
+            .iter()
+            .map(|subfeature| FeatureEarnedValue::as_of(subfeature, status_date))
+            .collect();
+        subfeatures.sort_by(|a, b| a.feature_id.cmp(&b.feature_id));
+
+        let figures = subfeatures
+            .iter()
+            .fold(own, |total, subfeature| total + subfeature.figures);
+
+        FeatureEarnedValue {
+            feature_id: feature.id.clone(),
+            figures,
+            subfeatures,
+        }
+    }
+}
+
+/// Earned value figures of a whole program
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ProgramEarnedValue {
+    pub program_id: Symbol,
+    pub status_date: DateTime<FixedOffset>,
+    #[serde(flatten)]
+    pub figures: EarnedValue,
+    pub root: FeatureEarnedValue,
+}
+
+impl ProgramEarnedValue {
+    pub fn as_of(program: &Program, status_date: DateTime<FixedOffset>) -> Self {
+        let root = FeatureEarnedValue::as_of(&program.root, status_date);
+        ProgramEarnedValue {
+            program_id: program.id.clone(),
+            status_date,
+            figures: root.figures,
+            root,
+        }
+    }
+}
+
+/// Earned value figures of every program in the graph, ordered by program ID
+pub fn earned_value(
+    graph: &ProgramGraph,
+    status_date: DateTime<FixedOffset>,
+) -> Vec<ProgramEarnedValue> {
+    let mut programs: Vec<ProgramEarnedValue> = graph
+        .programs
+        .iter()
+        .map(|program| ProgramEarnedValue::as_of(program, status_date))
+        .collect();
+    programs.sort_by(|a, b| a.program_id.cmp(&b.program_id));
+    programs
+}
+
+/// The fraction of the feature's time that has elapsed at the status date, from 0.0 to 1.0
+fn planned_progress(feature: &Feature, status_date: DateTime<FixedOffset>) -> f64 {
+    if status_date >= feature.end_date {
+        1.0
+    } else if status_date <= feature.start_date {
+        0.0
+    } else {
+        let elapsed = status_date - feature.start_date;
+        let planned = feature.end_date - feature.start_date;
+        elapsed.num_seconds() as f64 / planned.num_seconds() as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::DateTime;
+    use indoc::indoc;
+
+    use super::{earned_value, ProgramEarnedValue};
+    use crate::testing::graph;
+
+    // figures as of the day Email is due
+    fn programs() -> Vec<ProgramEarnedValue> {
+        let graph = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-01-01T00:00:00.000Z 2023-01-11T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email cost=100 actual=120
+            2023-01-01T00:00:00.000Z 2023-01-21T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Calendar cost=200 percent=25
+            2023-02-01T00:00:00.000Z 2023-02-28T00:00:00.000Z program1 InProgress TeamD ProductivitySuite->Chat cost=50
+        "});
+        let status_date = DateTime::parse_from_rfc3339("2023-01-11T00:00:00.000Z")
+            .expect("test dates should be checked");
+        earned_value(&graph, status_date)
+    }
+
+    #[test]
+    fn test_earned_value_rollup() {
+        let programs = programs();
+        assert_eq!(programs.len(), 1);
+
+        let figures = programs[0].figures;
+        assert_eq!(figures.budget_at_completion, 350.0);
+        // Email is due (100), Calendar is half way (100), Chat has not started (0)
+        assert_eq!(figures.planned_value, 200.0);
+        // Email is Complete (100), Calendar is 25% done (50)
+        assert_eq!(figures.earned_value, 150.0);
+        assert_eq!(figures.actual_cost, Some(120.0));
+        assert_eq!(figures.schedule_performance_index, Some(0.75));
+        // only Email has actuals, and it is over budget (100 / 120)
+        assert_eq!(figures.cost_performance_index, Some(100.0 / 120.0));
+    }
+
+    #[test]
+    fn test_indexes_without_figures() {
+        let programs = programs();
+        let chat = programs[0]
+            .root
+            .subfeatures
+            .iter()
+            .find(|feature| feature.feature_id == "Chat")
+            .expect("Chat is a subfeature");
+        assert_eq!(chat.figures.planned_value, 0.0);
+        assert_eq!(chat.figures.schedule_performance_index, None);
+        assert_eq!(chat.figures.cost_performance_index, None);
+    }
+}
@@ -1,9 +1,13 @@
-/// This code defines an error enum for the ProgramIngester module, named ProgramIngesterError. The enum has three variants:
-/// InvalidProgramInput: This variant is used when the input to the program is not valid, and it carries a string message describing the error.
-/// InvalidTimestamp: This variant is used when the timestamp in the input cannot be parsed, and it carries an underlying error of type chrono::ParseError.
-/// IoError: This variant is used when an I/O operation fails, and it carries an underlying error of type io::Error.
-/// The Error trait and the #[derive(Error, Debug)] attribute are from the thiserror crate,
-/// and they allow for convenient error handling and formatting of error messages.
+//! This code defines an error enum for the ProgramIngester module, named ProgramIngesterError. The enum has the following variants:
+//! InvalidProgramInput: This variant is used when the input to the program is not valid, and it carries a string message describing the error.
+//! InvalidTimestamp: This variant is used when the timestamp in the input cannot be parsed, and it carries an underlying error of type chrono::ParseError.
+//! IoError: This variant is used when an I/O operation fails, and it carries an underlying error of type io::Error.
+//! InvalidJson: This variant is used when JSON (eg: a stored baseline) cannot be read or written, and it carries an underlying error of type serde_json::Error.
+//! InvalidCsv: This variant is used when a CSV input cannot be read, and it carries an underlying error of type csv::Error.
+//! InvalidXml: This variant is used when an XML input (eg: a Microsoft Project export) cannot be parsed, and it carries an underlying error of type roxmltree::Error.
+//! Located: This variant wraps another error with where it happened (eg: `plan.txt:12`), so problems in merged inputs can be traced back to their file and line.
+//! The Error trait and the #[derive(Error, Debug)] attribute are from the thiserror crate,
+//! and they allow for convenient error handling and formatting of error messages.
 
 use std::io;
 
@@ -25,4 +29,29 @@ pub enum ProgramIngesterError {
         #[from]
         source: io::Error,
     },
+
+    #[error("The JSON could not be processed: {source}")]
+    InvalidJson {
+        #[from]
+        source: serde_json::Error,
+    },
+
+    #[error("The CSV could not be processed: {source}")]
+    InvalidCsv {
+        #[from]
+        source: csv::Error,
+    },
+
+    #[error("The XML could not be processed: {source}")]
+    InvalidXml {
+        #[from]
+        source: roxmltree::Error,
+    },
+
+    #[error("{location}: {error}")]
+    Located {
+        location: String,
+        #[source]
+        error: Box<ProgramIngesterError>,
+    },
 }
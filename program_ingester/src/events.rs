@@ -0,0 +1,295 @@
+//! Events change features over time. They are lines of the input, starting with [EVENT_MARKER] and the event timestamp:
+//!
+//! - `@2023-02-01T00:00:00Z create <feature line>` adds a feature (or replaces the feature with the same ID)
+//! - `@2023-03-01T00:00:00Z update Email status=Complete team=TeamD` changes some fields of a feature
+//! - `@2023-04-01T00:00:00Z reparent EmailSearch Email` moves a feature under another parent (`null` makes it a root)
+//! - `@2023-05-01T00:00:00Z delete Chat` removes a feature
+//!
+//! The fields of an update are `start=`, `end=`, `program=`, `status=`, `team=`, `kind=` (task or milestone),
+//! and the attributes of the feature line: `percent=`, `cost=`, `actual=` and `estimate=`.
+//!
+//! Lines without the marker are creates without a timestamp, so they come before every timestamped event.
+//! See [IncrementalBuilder::apply](crate::incremental::IncrementalBuilder::apply) for how events are applied.
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    errors::ProgramIngesterError,
+    input::{
+        parse_cost, parse_percent, FeatureID, FeatureKind, Provenance, RawFeature,
+        ThreePointEstimate,
+    },
+    symbol::{Interner, Symbol},
+};
+
+/// The marker at the start of event lines, followed by the event timestamp
+pub const EVENT_MARKER: &str = "@";
+
+/// A change to the features, at a point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// When the change happened
+    ///
+    /// If it is set to `None`, the event is part of the initial state, before any timestamped event
+    pub timestamp: Option<DateTime<FixedOffset>>,
+
+    pub action: Action,
+
+    /// Where the event was read from
+    pub provenance: Option<Provenance>,
+}
+
+impl Event {
+    /// An event without a timestamp, that adds the feature
+    pub fn create(feature: RawFeature) -> Self {
+        Event {
+            timestamp: None,
+            provenance: feature.provenance.clone(),
+            action: Action::Create(Box::new(feature)),
+        }
+    }
+
+    /// Record where the event (and the feature it creates) was read from
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        if let Action::Create(feature) = &mut self.action {
+            feature.provenance = Some(provenance.clone());
+        }
+        self.provenance = Some(provenance);
+        self
+    }
+
+    /// Share the names of the event with the features of the interner
+    pub fn intern(&mut self, interner: &mut Interner) {
+        match &mut self.action {
+            Action::Create(feature) => feature.intern(interner),
+            Action::Update { feature_id, fields } => {
+                interner.share(feature_id);
+                for field in fields.iter_mut() {
+                    match field {
+                        Field::ProgramId(name)
+                        | Field::ProgressStatus(name)
+                        | Field::AssignedTeam(name) => interner.share(name),
+                        _ => {}
+                    }
+                }
+            }
+            Action::Reparent {
+                feature_id,
+                parent_id,
+            } => {
+                interner.share(feature_id);
+                if let Some(parent_id) = parent_id {
+                    interner.share(parent_id);
+                }
+            }
+            Action::Delete { feature_id } => interner.share(feature_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Add the feature, or replace the feature with the same ID
+    Create(Box<RawFeature>),
+    /// Change some fields of the feature
+    Update {
+        feature_id: FeatureID,
+        fields: Vec<Field>,
+    },
+    /// Move the feature under another parent, or make it a root
+    Reparent {
+        feature_id: FeatureID,
+        parent_id: Option<FeatureID>,
+    },
+    /// Remove the feature, its subfeatures are left without a parent
+    Delete { feature_id: FeatureID },
+}
+
+impl Action {
+    /// The ID of the feature the action is on
+    pub fn feature_id(&self) -> &FeatureID {
+        match self {
+            Action::Create(feature) => &feature.id,
+            Action::Update { feature_id, .. }
+            | Action::Reparent { feature_id, .. }
+            | Action::Delete { feature_id } => feature_id,
+        }
+    }
+}
+
+/// A new value for a field of a feature
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    StartDate(DateTime<FixedOffset>),
+    EndDate(DateTime<FixedOffset>),
+    ProgramId(Symbol),
+    ProgressStatus(Symbol),
+    AssignedTeam(Symbol),
+    Kind(FeatureKind),
+    PercentComplete(u8),
+    PlannedCost(f64),
+    ActualCost(f64),
+    Estimate(ThreePointEstimate),
+}
+
+impl Field {
+    pub fn apply(&self, feature: &mut RawFeature) {
+        match self {
+            Field::StartDate(start_date) => feature.start_date = *start_date,
+            Field::EndDate(end_date) => feature.end_date = *end_date,
+            Field::ProgramId(program_id) => feature.program_id = program_id.clone(),
+            Field::ProgressStatus(status) => feature.progress_status = status.clone(),
+            Field::AssignedTeam(team) => feature.assigned_team = team.clone(),
+            Field::Kind(kind) => feature.kind = *kind,
+            Field::PercentComplete(percent) => feature.percent_complete = Some(*percent),
+            Field::PlannedCost(cost) => feature.planned_cost = Some(*cost),
+            Field::ActualCost(cost) => feature.actual_cost = Some(*cost),
+            Field::Estimate(estimate) => feature.estimate = Some(*estimate),
+        }
+    }
+
+    // parse `name=value`, `line` is only used in error messages
+    fn parse(field: &str, line: &str) -> Result<Self, ProgramIngesterError> {
+        let Some((name, value)) = field.split_once('=') else {
+            return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                "The field '{field}' in '{line}' needs to be name=value"
+            )));
+        };
+
+        Ok(match name {
+            "start" => Field::StartDate(DateTime::parse_from_rfc3339(value)?),
+            "end" => Field::EndDate(DateTime::parse_from_rfc3339(value)?),
+            "program" => Field::ProgramId(value.into()),
+            "status" => Field::ProgressStatus(value.into()),
+            "team" => Field::AssignedTeam(value.into()),
+            "kind" => Field::Kind(match value {
+                "task" => FeatureKind::Task,
+                "milestone" => FeatureKind::Milestone,
+                _ => {
+                    return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                        "The kind '{value}' in '{line}' is not one of 'task', 'milestone'"
+                    )))
+                }
+            }),
+            "percent" => Field::PercentComplete(parse_percent(value, line)?),
+            "cost" => Field::PlannedCost(parse_cost(value, line)?),
+            "actual" => Field::ActualCost(parse_cost(value, line)?),
+            "estimate" => Field::Estimate(ThreePointEstimate::from_str(value)?),
+            _ => {
+                return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                    "The field '{name}' in '{line}' is not one of 'start', 'end', 'program', 'status', 'team', 'kind', 'percent', 'cost', 'actual', 'estimate'"
+                )))
+            }
+        })
+    }
+}
+
+impl FromStr for Event {
+    type Err = crate::errors::ProgramIngesterError;
+
+    /// Try to turn an event line into an event
+    ///
+    /// Example: `@2023-03-01T00:00:00Z update Email status=Complete`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = |reason: &str| {
+            ProgramIngesterError::InvalidProgramInput(format!("The event '{s}' {reason}"))
+        };
+
+        let Some(rest) = s.strip_prefix(EVENT_MARKER) else {
+            return Err(invalid(&format!("needs to start with '{EVENT_MARKER}'")));
+        };
+        let mut parts = rest.splitn(3, ' ');
+        let timestamp = DateTime::parse_from_rfc3339(parts.next().unwrap_or_default())?;
+        let kind = parts.next().unwrap_or_default();
+        let arguments = parts.next().unwrap_or_default();
+
+        let action = match kind {
+            "create" => Action::Create(Box::new(RawFeature::from_str(arguments)?)),
+            "update" => {
+                let mut fields = arguments.split(' ');
+                let feature_id = fields.next().unwrap_or_default();
+                let fields = fields
+                    .map(|field| Field::parse(field, s))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if feature_id.is_empty() || fields.is_empty() {
+                    return Err(invalid("needs a feature and at least one field"));
+                }
+                Action::Update {
+                    feature_id: feature_id.into(),
+                    fields,
+                }
+            }
+            "reparent" => match arguments.split(' ').collect::<Vec<_>>()[..] {
+                [feature_id, parent_id] if !feature_id.is_empty() => Action::Reparent {
+                    feature_id: feature_id.into(),
+                    parent_id: match parent_id {
+                        "null" => None,
+                        id => Some(id.into()),
+                    },
+                },
+                _ => return Err(invalid("needs a feature and its new parent (or null)")),
+            },
+            "delete" => match arguments.split(' ').collect::<Vec<_>>()[..] {
+                [feature_id] if !feature_id.is_empty() => Action::Delete {
+                    feature_id: feature_id.into(),
+                },
+                _ => return Err(invalid("needs a feature")),
+            },
+            _ => {
+                return Err(invalid(
+                    "needs to be one of 'create', 'update', 'reparent', 'delete'",
+                ))
+            }
+        };
+
+        Ok(Event {
+            timestamp: Some(timestamp),
+            action,
+            provenance: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::{Action, Event, Field};
+
+    #[test]
+    fn test_parsing_events() {
+        let update =
+            Event::from_str("@2023-03-01T00:00:00Z update Email status=Complete percent=100")
+                .expect("update should parse");
+        assert_eq!(
+            update.action,
+            Action::Update {
+                feature_id: "Email".into(),
+                fields: vec![
+                    Field::ProgressStatus("Complete".into()),
+                    Field::PercentComplete(100)
+                ],
+            }
+        );
+
+        let reparent = Event::from_str("@2023-03-01T00:00:00Z reparent EmailSearch null")
+            .expect("reparent should parse");
+        assert_eq!(
+            reparent.action,
+            Action::Reparent {
+                feature_id: "EmailSearch".into(),
+                parent_id: None,
+            }
+        );
+
+        let create = Event::from_str("@2023-03-01T00:00:00Z create 2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email")
+            .expect("create should parse");
+        assert!(matches!(create.action, Action::Create(feature) if feature.id == "Email"));
+
+        assert!(Event::from_str("@2023-03-01T00:00:00Z delete").is_err());
+        assert!(Event::from_str("@2023-03-01T00:00:00Z update Email colour=red").is_err());
+        assert!(Event::from_str("@2023-03-01T00:00:00Z rename Email Mail").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Classify the features of a [ProgramGraph] as of a status date, to find the work that is late or likely to be.
+//!
+//! The status date comes from a [Clock], so that reports can be reproduced (eg: in tests) with a [FixedClock].
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Serialize;
+
+use crate::{
+    input::FeatureID,
+    output::{Feature, ProgramGraph},
+    symbol::Symbol,
+};
+
+/// A source of the current date
+pub trait Clock {
+    fn now(&self) -> DateTime<FixedOffset>;
+}
+
+/// The system's clock, in UTC
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        Utc::now().into()
+    }
+}
+
+/// A clock that is stopped at a given date
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<FixedOffset>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        self.0
+    }
+}
+
+/// How a feature is tracking at the status date
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Health {
+    /// The work is done
+    Complete,
+    /// Past its end_date and not complete
+    Overdue,
+    /// Past its start_date and not started
+    ShouldHaveStarted,
+    /// The time left until the end_date is shorter than the remaining work needs
+    AtRisk,
+    OnTrack,
+}
+
+impl Health {
+    /// Whether the feature needs attention
+    pub fn is_flagged(&self) -> bool {
+        matches!(
+            self,
+            Health::Overdue | Health::ShouldHaveStarted | Health::AtRisk
+        )
+    }
+
+    /// Classify a feature as of the status date
+    ///
+    /// The remaining work is estimated from the percent_complete as that share of the feature's planned duration,
+    /// so features without a percent_complete are never considered at risk.
+    pub fn of(feature: &Feature, as_of: DateTime<FixedOffset>) -> Self {
+        if feature.is_complete() {
+            Health::Complete
+        } else if as_of > feature.end_date {
+            Health::Overdue
+        } else if as_of > feature.start_date && !feature.is_started() {
+            Health::ShouldHaveStarted
+        } else if feature.percent_complete.is_some() && as_of > feature.start_date {
+            let planned = (feature.end_date - feature.start_date).num_seconds() as f64;
+            let remaining_work = planned * (1.0 - feature.progress());
+            let remaining_time = (feature.end_date - as_of).num_seconds() as f64;
+            if remaining_work > remaining_time {
+                Health::AtRisk
+            } else {
+                Health::OnTrack
+            }
+        } else {
+            Health::OnTrack
+        }
+    }
+}
+
+/// The health of a single feature
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FeatureHealth {
+    pub program_id: Symbol,
+    #[serde(rename = "feature")]
+    pub feature_id: FeatureID,
+    pub health: Health,
+    pub start_date: DateTime<FixedOffset>,
+    pub end_date: DateTime<FixedOffset>,
+}
+
+/// The health of every feature in a [ProgramGraph], grouped by assigned_team
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct HealthReport {
+    pub as_of: DateTime<FixedOffset>,
+
+    /// Features per assigned_team, ordered by end_date
+    pub teams: BTreeMap<Symbol, Vec<FeatureHealth>>,
+}
+
+impl HealthReport {
+    pub fn new(graph: &ProgramGraph, clock: &impl Clock) -> Self {
+        let as_of = clock.now();
+        let mut teams: BTreeMap<Symbol, Vec<FeatureHealth>> = BTreeMap::new();
+
+        for program in graph.programs.iter() {
+            for feature in program.root.flatten() {
+                teams
+                    .entry(feature.assigned_team.clone())
+                    .or_default()
+                    .push(FeatureHealth {
+                        program_id: program.id.clone(),
+                        feature_id: feature.id.clone(),
+                        health: Health::of(feature, as_of),
+                        start_date: feature.start_date,
+                        end_date: feature.end_date,
+                    });
+            }
+        }
+
+        for features in teams.values_mut() {
+            features.sort_by(|a, b| (a.end_date, &a.feature_id).cmp(&(b.end_date, &b.feature_id)));
+        }
+
+        HealthReport { as_of, teams }
+    }
+
+    /// Only the features that need attention
+    pub fn flagged(&self) -> impl Iterator<Item = &FeatureHealth> {
+        self.teams
+            .values()
+            .flatten()
+            .filter(|feature| feature.health.is_flagged())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, FixedOffset};
+    use indoc::indoc;
+
+    use super::{FixedClock, Health, HealthReport};
+    use crate::testing::graph;
+
+    const PLAN: &str = indoc! {"
+        2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite percent=50
+        2023-01-01T00:00:00.000Z 2023-02-28T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email
+        2023-01-01T00:00:00.000Z 2023-02-28T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Calendar
+        2023-02-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 NotStarted TeamC ProductivitySuite->Chat
+        2023-03-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Tasks percent=10
+    "};
+
+    fn as_of() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2023-03-21T00:00:00.000Z")
+            .expect("test dates should be checked")
+    }
+
+    #[test]
+    fn test_health_of_features() {
+        let graph = graph(PLAN);
+        let health = |feature_id: &str| {
+            let feature = graph.programs[0]
+                .root
+                .flatten()
+                .into_iter()
+                .find(|feature| feature.id == feature_id)
+                .expect("the feature is in the plan");
+            Health::of(feature, as_of())
+        };
+
+        assert_eq!(health("ProductivitySuite"), Health::OnTrack);
+        assert_eq!(health("Calendar"), Health::Complete);
+        assert_eq!(health("Email"), Health::Overdue);
+        assert_eq!(health("Chat"), Health::ShouldHaveStarted);
+        // 90% of the work is left, with a third of the time
+        assert_eq!(health("Tasks"), Health::AtRisk);
+    }
+
+    #[test]
+    fn test_health_report_by_team() {
+        let report = HealthReport::new(&graph(PLAN), &FixedClock(as_of()));
+        let features = |team: &str| -> Vec<&str> {
+            report.teams[team]
+                .iter()
+                .map(|feature| feature.feature_id.as_str())
+                .collect()
+        };
+
+        assert_eq!(report.as_of, as_of());
+        assert_eq!(features("TeamA"), ["ProductivitySuite"]);
+        assert_eq!(features("TeamB"), ["Calendar", "Email"]);
+        // in end_date order
+        assert_eq!(features("TeamC"), ["Tasks", "Chat"]);
+        assert_eq!(report.flagged().count(), 3);
+    }
+}
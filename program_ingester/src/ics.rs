@@ -0,0 +1,1326 @@
+//! iCalendar (RFC 5545) feeds of the features, so teams can see their deliverables in their calendar apps,
+//! and sub-plans kept as shared calendars can be read as features.
+//!
+//! [Ics] is a [Renderer], so it takes the same [RenderOptions] as the charts: `team` gives a per-team feed,
+//! `milestones` and `leaves` leave out the summary features, and `from` and `until` pick a time range.
+//! Each feature is a `VEVENT` with a UID from its program and feature IDs, so a calendar app updates the event
+//! (instead of adding another) when the plan changes.
+//!
+//! [IcsFormat] is a [SourceFormat] that reads the `VEVENT`s of a calendar as features, see [IcsMapping] for
+//! where their fields come from. It reads the feeds of [Ics] back as the same features.
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    io::{self, BufRead, Write},
+};
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, Offset, TimeZone,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use crate::{
+    errors::ProgramIngesterError,
+    input::{FeatureKind, Provenance, RawFeature},
+    output::ProgramGraph,
+    render::{Chart, RenderOptions, Renderer},
+    source::{FeatureSource, SourceFormat},
+    symbol::Interner,
+};
+
+// lines longer than this many bytes are folded onto continuation lines
+const LINE_LIMIT: usize = 75;
+
+// the most periods (eg: weeks) of a recurrence rule that are looked at
+const MAX_PERIODS: u32 = 100_000;
+
+/// An iCalendar feed, a `VEVENT` per feature
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ics {
+    /// When the feed is generated (its `DTSTAMP`), now when `None`. Set it to get the same feed from the same plan
+    pub stamp: Option<DateTime<FixedOffset>>,
+}
+
+impl Renderer for Ics {
+    fn name(&self) -> &str {
+        "ics"
+    }
+
+    fn extension(&self) -> &str {
+        "ics"
+    }
+
+    /// Milestones are events without a duration. The `SEQUENCE` of an event is a hash of its content,
+    /// so calendar apps see that the event changed when its feature does.
+    fn render(
+        &self,
+        graph: &ProgramGraph,
+        options: &RenderOptions,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//program_ingester//EN".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            format!(
+                "X-WR-CALNAME:{}",
+                text(options.team.as_deref().unwrap_or("Programs"))
+            ),
+        ];
+        let stamp = date_time(self.stamp.unwrap_or_else(|| Utc::now().into()));
+        for row in Chart::new(graph, options).iter().flat_map(Chart::rows) {
+            let feature = row.feature;
+            let path = row
+                .ancestors
+                .iter()
+                .chain([&&feature.id])
+                .map(|id| id.as_str())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            // the properties that describe the feature, which the SEQUENCE is a hash of
+            let mut event = vec![format!(
+                "UID:{}@program_ingester",
+                text(&format!("{}/{}", row.program_id, feature.id))
+            )];
+            event.push(format!("DTSTART:{}", date_time(feature.start_date)));
+            if !feature.is_milestone() {
+                event.push(format!("DTEND:{}", date_time(feature.end_date)));
+            }
+            event.push(format!(
+                "SUMMARY:{}",
+                text(&format!(
+                    "{} ({}, {})",
+                    row.label(options.wbs),
+                    feature.assigned_team,
+                    feature.progress_status
+                ))
+            ));
+            event.push(format!(
+                "CATEGORIES:{},{}",
+                text(&feature.assigned_team),
+                text(&feature.progress_status)
+            ));
+            event.push(format!(
+                "DESCRIPTION:{}",
+                text(&format!("{}: {path}", row.program_id))
+            ));
+            // the fields of the feature, for reading the feed back
+            event.push(format!("X-PROGRAM:{}", text(row.program_id)));
+            event.push(format!("X-FEATURE:{}", text(&feature.id)));
+            if let Some(parent_id) = row.parent_id {
+                event.push(format!("X-PARENT:{}", text(parent_id)));
+            }
+            if feature.is_milestone() {
+                event.push("X-MILESTONE:TRUE".to_string());
+            }
+            let sequence = sequence(&event);
+            event.splice(
+                1..1,
+                [format!("DTSTAMP:{stamp}"), format!("SEQUENCE:{sequence}")],
+            );
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.extend(event);
+            lines.push("END:VEVENT".to_string());
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        for line in lines {
+            write!(writer, "{}\r\n", fold(&line))?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a field of the features comes from in an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IcsField {
+    /// The value of a property (eg: `X-PROGRAM`) of the event, or else of the calendar (eg: `X-WR-CALNAME`)
+    Property(String),
+    /// One of the event's `CATEGORIES`, from 0
+    Category(usize),
+    /// The same value for every event
+    Value(String),
+}
+
+impl IcsField {
+    fn value(&self, event: &Component, calendar: &Component) -> Option<String> {
+        let value = match self {
+            IcsField::Property(name) => event
+                .get(name)
+                .or_else(|| calendar.get(name))
+                .map(|property| unescape(&property.value)),
+            IcsField::Category(index) => event.categories().into_iter().nth(*index),
+            IcsField::Value(value) => Some(value.clone()),
+        };
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+impl Display for IcsField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IcsField::Property(name) => write!(f, "{name}"),
+            IcsField::Category(index) => write!(f, "CATEGORIES[{index}]"),
+            IcsField::Value(value) => write!(f, "'{value}'"),
+        }
+    }
+}
+
+/// Where the fields of the features come from, each is the first of its [IcsField]s that the event has.
+///
+/// By default they are the properties written by [Ics], with the `SUMMARY` as the ID and the calendar's name as the program
+/// when those are missing. A feature without a parent is a root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcsMapping {
+    pub id: Vec<IcsField>,
+    pub parent: Vec<IcsField>,
+    pub program: Vec<IcsField>,
+    pub team: Vec<IcsField>,
+    pub status: Vec<IcsField>,
+}
+
+impl Default for IcsMapping {
+    fn default() -> Self {
+        let property = |name: &str| IcsField::Property(name.to_string());
+        IcsMapping {
+            id: vec![property("X-FEATURE"), property("SUMMARY")],
+            parent: vec![property("X-PARENT")],
+            program: vec![property("X-PROGRAM"), property("X-WR-CALNAME")],
+            team: vec![IcsField::Category(0)],
+            status: vec![IcsField::Category(1)],
+        }
+    }
+}
+
+/// The `VEVENT`s of an iCalendar input, a feature per event
+///
+/// The start and end come from `DTSTART` and `DTEND` (or `DURATION`). Times with a `TZID` are read in that time zone,
+/// which needs to be an IANA time zone (eg: `Europe/London`), floating times are read as UTC,
+/// and all day events start and end at midnight UTC. Events with `X-MILESTONE:TRUE`, or without a duration, are milestones.
+///
+/// Recurring events are expanded into a feature per occurrence that overlaps the window from `from` until `until`,
+/// with the ID of the event and the date of the occurrence, eg: `Review@2023-01-02`. `DTSTART` is always the first occurrence,
+/// `EXDATE`s are left out, and the events with a `RECURRENCE-ID` replace their occurrence. The rules can have a `DAILY`,
+/// `WEEKLY`, `MONTHLY` or `YEARLY` frequency, an `INTERVAL`, a `COUNT` or `UNTIL`, and weekly rules can have weekdays (`BYDAY`)
+/// and a week start (`WKST`). Occurrences repeat at the same local time, also across daylight saving changes.
+/// Rules without a `COUNT` or `UNTIL` need an `until`, and rules that repeat for more than 100,000 periods are not valid.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IcsFormat {
+    pub mapping: IcsMapping,
+    pub from: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+impl SourceFormat for IcsFormat {
+    fn name(&self) -> &str {
+        "ics"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ics", "ical"]
+    }
+
+    fn features<'a>(
+        &self,
+        name: &str,
+        mut reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn FeatureSource + 'a> {
+        let mut text = String::new();
+        let features = reader
+            .read_to_string(&mut text)
+            .map_err(|error| ProgramIngesterError::Located {
+                location: name.into(),
+                error: Box::new(error.into()),
+            })
+            .and_then(|_| self.read(name, &text));
+        match features {
+            Ok(features) => Box::new(features.into_iter().map(Ok)),
+            Err(error) => Box::new(std::iter::once(Err(error))),
+        }
+    }
+}
+
+impl IcsFormat {
+    fn read(&self, name: &str, text: &str) -> Result<Vec<RawFeature>, ProgramIngesterError> {
+        let (calendar, events) = Component::parse(name, text)?;
+
+        // the occurrences replaced by another event, by UID
+        let mut replaced = HashSet::new();
+        for event in events.iter() {
+            if let (Some(uid), Some(recurrence_id)) = (event.get("UID"), event.get("RECURRENCE-ID"))
+            {
+                let original = recurrence_id
+                    .date_time()
+                    .map_err(|error| event.located(error))?;
+                replaced.insert((uid.value.clone(), original));
+            }
+        }
+
+        let mut interner = Interner::new();
+        let mut features = vec![];
+        for event in events.iter() {
+            let feature = self
+                .feature(event, &calendar, &mut interner)
+                .map_err(|error| event.located(error))?;
+            match (event.get("RRULE"), event.get("RECURRENCE-ID")) {
+                (_, Some(recurrence_id)) => {
+                    let original = recurrence_id
+                        .date_time()
+                        .map_err(|error| event.located(error))?;
+                    if self.in_window(&feature) {
+                        features.push(occurrence(
+                            &feature,
+                            original,
+                            feature.start_date,
+                            &mut interner,
+                        ));
+                    }
+                }
+                (Some(rule), None) => {
+                    let rule = rule
+                        .value
+                        .parse::<Rule>()
+                        .map_err(|error| event.located(error))?;
+                    if rule.count.is_none() && rule.until.is_none() && self.until.is_none() {
+                        return Err(event.located(invalid(format!(
+                            "The event '{}' repeats forever, it needs a window to be expanded in",
+                            feature.id
+                        ))));
+                    }
+                    let mut excluded = HashSet::new();
+                    for property in event.get_all("EXDATE") {
+                        let zone = property.zone().map_err(|error| event.located(error))?;
+                        for value in property.value.split(',') {
+                            excluded.insert(
+                                parse_date_time(value, zone)
+                                    .map_err(|error| event.located(error))?,
+                            );
+                        }
+                    }
+                    let uid = event
+                        .get("UID")
+                        .map(|uid| uid.value.clone())
+                        .unwrap_or_default();
+                    // the occurrences repeat at the local time of the first one
+                    let (start, zone) = event
+                        .get("DTSTART")
+                        .map(Property::local)
+                        .transpose()
+                        .map_err(|error| event.located(error))?
+                        .ok_or_else(|| {
+                            event.located(invalid("The event needs a DTSTART".into()))
+                        })?;
+                    let starts = rule
+                        .starts(start, zone, self.until)
+                        .map_err(|error| event.located(error))?;
+                    for start in starts {
+                        if excluded.contains(&start) || replaced.contains(&(uid.clone(), start)) {
+                            continue;
+                        }
+                        let occurrence = occurrence(&feature, start, start, &mut interner);
+                        if self.in_window(&occurrence) {
+                            features.push(occurrence);
+                        }
+                    }
+                }
+                (None, None) => features.push(feature),
+            }
+        }
+        Ok(features)
+    }
+
+    // the feature of an event, at the time of its first occurrence
+    fn feature(
+        &self,
+        event: &Component,
+        calendar: &Component,
+        interner: &mut Interner,
+    ) -> Result<RawFeature, ProgramIngesterError> {
+        let first =
+            |fields: &[IcsField]| fields.iter().find_map(|field| field.value(event, calendar));
+        let required = |fields: &[IcsField], what: &str| {
+            first(fields).ok_or_else(|| {
+                let fields: Vec<String> = fields.iter().map(ToString::to_string).collect();
+                invalid(format!(
+                    "The event needs a {what}, from {}",
+                    fields.join(" or ")
+                ))
+            })
+        };
+
+        let id = required(&self.mapping.id, "feature ID")?;
+        let start = event
+            .get("DTSTART")
+            .ok_or_else(|| invalid(format!("The event '{id}' needs a DTSTART")))?;
+        let start_date = start.date_time()?;
+        let end_date = match (event.get("DTEND"), event.get("DURATION")) {
+            (Some(end), _) => end.date_time()?,
+            (None, Some(duration)) => start_date + parse_duration(&duration.value)?,
+            // all day events last the day, and events at a time have no duration
+            (None, None) if start.is_date() => start_date + Duration::days(1),
+            (None, None) => start_date,
+        };
+        if end_date < start_date {
+            return Err(invalid(format!("The event '{id}' ends before it starts")));
+        }
+        let milestone = event
+            .get("X-MILESTONE")
+            .map(|property| property.value.eq_ignore_ascii_case("TRUE"));
+
+        Ok(RawFeature {
+            id: interner.intern(&id),
+            parent_id: first(&self.mapping.parent).map(|parent_id| interner.intern(&parent_id)),
+            program_id: interner.intern(&required(&self.mapping.program, "program")?),
+            progress_status: interner.intern(&required(&self.mapping.status, "status")?),
+            assigned_team: interner.intern(&required(&self.mapping.team, "team")?),
+            start_date,
+            end_date,
+            kind: match milestone {
+                Some(true) => FeatureKind::Milestone,
+                None if start_date == end_date => FeatureKind::Milestone,
+                _ => FeatureKind::Task,
+            },
+            percent_complete: None,
+            planned_cost: None,
+            actual_cost: None,
+            estimate: None,
+            provenance: Some(event.provenance.clone()),
+        })
+    }
+
+    // whether the occurrence overlaps the window
+    fn in_window(&self, feature: &RawFeature) -> bool {
+        self.from.is_none_or(|from| feature.end_date >= from)
+            && self.until.is_none_or(|until| feature.start_date <= until)
+    }
+}
+
+// an occurrence of a recurring event, named after the date it was planned on
+fn occurrence(
+    feature: &RawFeature,
+    original: DateTime<FixedOffset>,
+    start: DateTime<FixedOffset>,
+    interner: &mut Interner,
+) -> RawFeature {
+    RawFeature {
+        id: interner.intern(&format!("{}@{}", feature.id, original.format("%Y-%m-%d"))),
+        start_date: start,
+        end_date: start + (feature.end_date - feature.start_date),
+        ..feature.clone()
+    }
+}
+
+fn invalid(message: String) -> ProgramIngesterError {
+    ProgramIngesterError::InvalidProgramInput(message)
+}
+
+// a property of a component, eg: `DTSTART;VALUE=DATE:20230101`
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    parameters: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Result<Self, ProgramIngesterError> {
+        // the value starts at the first ':' that is not in a quoted parameter
+        let mut quoted = false;
+        let colon = line
+            .char_indices()
+            .find(|&(_, char)| {
+                if char == '"' {
+                    quoted = !quoted;
+                }
+                char == ':' && !quoted
+            })
+            .map(|(index, _)| index)
+            .ok_or_else(|| invalid(format!("The line '{line}' needs a ':' before its value")))?;
+
+        let mut parts = line[..colon].split(';');
+        let name = parts.next().unwrap_or_default().trim().to_ascii_uppercase();
+        let parameters = parts
+            .filter_map(|parameter| parameter.split_once('='))
+            .map(|(name, value)| {
+                (
+                    name.trim().to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                )
+            })
+            .collect();
+        Ok(Property {
+            name,
+            parameters,
+            value: line[colon + 1..].to_string(),
+        })
+    }
+
+    // a DATE, rather than a DATE-TIME
+    fn is_date(&self) -> bool {
+        self.parameters
+            .iter()
+            .any(|(name, value)| name == "VALUE" && value.eq_ignore_ascii_case("DATE"))
+            || self.value.trim().len() == 8
+    }
+
+    fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // the time zone of the value, from its TZID
+    fn zone(&self) -> Result<Zone, ProgramIngesterError> {
+        match self.parameter("TZID") {
+            None => Ok(Zone::Utc),
+            // a leading '/' marks a globally unique time zone name
+            Some(tzid) => tzid
+                .trim_start_matches('/')
+                .parse()
+                .map(Zone::Named)
+                .map_err(|_| {
+                    invalid(format!(
+                        "The time zone '{tzid}' is not an IANA time zone, eg: Europe/London"
+                    ))
+                }),
+        }
+    }
+
+    fn local(&self) -> Result<(NaiveDateTime, Zone), ProgramIngesterError> {
+        parse_local(&self.value, self.zone()?)
+    }
+
+    fn date_time(&self) -> Result<DateTime<FixedOffset>, ProgramIngesterError> {
+        parse_date_time(&self.value, self.zone()?)
+    }
+}
+
+// the time zone that local times are read in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Zone {
+    Utc,
+    Named(Tz),
+}
+
+impl Zone {
+    fn resolve(&self, local: NaiveDateTime) -> DateTime<FixedOffset> {
+        let tz = match self {
+            Zone::Utc => return Utc.from_utc_datetime(&local).into(),
+            Zone::Named(tz) => tz,
+        };
+        let time = match tz.from_local_datetime(&local).earliest() {
+            Some(time) => time,
+            // the time is skipped by a daylight saving change, so it is read with the offset from before the change
+            None => {
+                let before = tz
+                    .offset_from_utc_datetime(&(local - Duration::days(1)))
+                    .fix();
+                tz.from_utc_datetime(&(local - Duration::seconds(before.local_minus_utc().into())))
+            }
+        };
+        time.with_timezone(&time.offset().fix())
+    }
+}
+
+// the properties of the calendar, or of one of its events
+#[derive(Debug, Clone)]
+struct Component {
+    provenance: Provenance,
+    properties: Vec<Property>,
+}
+
+impl Component {
+    // the calendar and its events, other components (eg: alarms and time zones) are skipped
+    fn parse(name: &str, text: &str) -> Result<(Component, Vec<Component>), ProgramIngesterError> {
+        let mut calendar: Option<Component> = None;
+        let mut events = vec![];
+        let mut open: Vec<String> = vec![];
+        for (provenance, line) in unfold(name, text) {
+            let located = |error| ProgramIngesterError::Located {
+                location: provenance.to_string(),
+                error: Box::new(error),
+            };
+            let property = Property::parse(&line).map_err(located)?;
+            let component = property.value.trim().to_ascii_uppercase();
+            match property.name.as_str() {
+                "BEGIN" => {
+                    match (open.as_slice(), component.as_str()) {
+                        ([], "VCALENDAR") if calendar.is_none() => {
+                            calendar = Some(Component::new(provenance.clone()))
+                        }
+                        ([], _) => {
+                            return Err(located(invalid(format!(
+                                "The input needs to be one VCALENDAR, not '{line}'"
+                            ))))
+                        }
+                        ([_], "VEVENT") => events.push(Component::new(provenance.clone())),
+                        _ => {}
+                    }
+                    open.push(component);
+                }
+                "END" => match open.pop() {
+                    Some(begun) if begun == component => {}
+                    begun => {
+                        return Err(located(invalid(format!(
+                            "'{line}' does not end {}",
+                            begun
+                                .map(|begun| format!("BEGIN:{begun}"))
+                                .unwrap_or_else(|| "anything".into())
+                        ))))
+                    }
+                },
+                _ => match (open.as_slice(), calendar.as_mut(), events.last_mut()) {
+                    ([_], Some(calendar), _) => calendar.properties.push(property),
+                    ([_, event], _, Some(last)) if event == "VEVENT" => {
+                        last.properties.push(property)
+                    }
+                    _ => {}
+                },
+            }
+        }
+        if let Some(begun) = open.last() {
+            return Err(invalid(format!("{name}: BEGIN:{begun} needs an END")));
+        }
+        let calendar = calendar.ok_or_else(|| invalid(format!("{name}: there is no VCALENDAR")))?;
+        Ok((calendar, events))
+    }
+
+    fn new(provenance: Provenance) -> Self {
+        Component {
+            provenance,
+            properties: vec![],
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    // the values of all the CATEGORIES properties, in order
+    fn categories(&self) -> Vec<String> {
+        self.get_all("CATEGORIES")
+            .flat_map(|property| split_list(&property.value))
+            .collect()
+    }
+
+    fn located(&self, error: ProgramIngesterError) -> ProgramIngesterError {
+        ProgramIngesterError::Located {
+            location: self.provenance.to_string(),
+            error: Box::new(error),
+        }
+    }
+}
+
+// join the folded lines back together, with where each line starts
+fn unfold(name: &str, text: &str) -> Vec<(Provenance, String)> {
+    let mut lines: Vec<(Provenance, String)> = vec![];
+    let mut offset = 0;
+    for (index, line) in text.split('\n').enumerate() {
+        let start = offset;
+        offset += line.len() + 1;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some((_, last))) => last.push_str(continued),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push((
+                Provenance {
+                    source: name.to_string(),
+                    line: index + 1,
+                    offset: start,
+                },
+                line.to_string(),
+            )),
+        }
+    }
+    lines
+}
+
+// the inverse of `text`
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(escaped) => unescaped.push(escaped),
+                None => unescaped.push('\\'),
+            },
+            char => unescaped.push(char),
+        }
+    }
+    unescaped
+}
+
+// the values of a list separated by commas that are not escaped
+fn split_list(text: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut value = String::new();
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                value.push(char);
+                value.extend(chars.next());
+            }
+            ',' => values.push(unescape(&std::mem::take(&mut value))),
+            char => value.push(char),
+        }
+    }
+    values.push(unescape(&value));
+    values
+}
+
+// a DATE (eg: 20230101) or DATE-TIME (eg: 20230101T090000Z, or 20230101T090000 in the zone)
+fn parse_date_time(value: &str, zone: Zone) -> Result<DateTime<FixedOffset>, ProgramIngesterError> {
+    let (local, zone) = parse_local(value, zone)?;
+    Ok(zone.resolve(local))
+}
+
+// the local time of a DATE or DATE-TIME, and its zone (dates and times ending in 'Z' are UTC)
+fn parse_local(value: &str, zone: Zone) -> Result<(NaiveDateTime, Zone), ProgramIngesterError> {
+    let value = value.trim();
+    Ok(match (value.len(), value.strip_suffix('Z')) {
+        (8, _) => (
+            NaiveDate::parse_from_str(value, "%Y%m%d")?
+                .and_hms_opt(0, 0, 0)
+                .unwrap_or_default(),
+            Zone::Utc,
+        ),
+        (_, Some(utc)) => (
+            NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?,
+            Zone::Utc,
+        ),
+        (_, None) => (NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?, zone),
+    })
+}
+
+// a DURATION, eg: P1W or P1DT2H30M
+fn parse_duration(value: &str) -> Result<Duration, ProgramIngesterError> {
+    let not_valid = || {
+        invalid(format!(
+            "The duration '{value}' is not like P1W or P1DT2H30M"
+        ))
+    };
+    let (sign, rest) = match value.trim().strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.trim().trim_start_matches('+')),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(not_valid)?;
+
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut time = false;
+    for char in rest.chars() {
+        match char {
+            '0'..='9' => number.push(char),
+            'T' if number.is_empty() && !time => time = true,
+            unit => {
+                let count: i64 = number.parse().map_err(|_| not_valid())?;
+                number.clear();
+                seconds += count
+                    * match (unit, time) {
+                        ('W', false) => 604_800,
+                        ('D', false) => 86_400,
+                        ('H', true) => 3_600,
+                        ('M', true) => 60,
+                        ('S', true) => 1,
+                        _ => return Err(not_valid()),
+                    };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return Err(not_valid());
+    }
+    Ok(Duration::seconds(sign * seconds))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// a recurrence rule (RRULE), eg: FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10
+#[derive(Debug, Clone)]
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<DateTime<FixedOffset>>,
+    weekdays: Vec<Weekday>,
+    week_start: Weekday,
+}
+
+impl std::str::FromStr for Rule {
+    type Err = ProgramIngesterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_supported = |part: &str| {
+            invalid(format!(
+                "The recurrence rule part '{part}' of '{s}' is not supported"
+            ))
+        };
+        let mut frequency = None;
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            weekdays: vec![],
+            week_start: Weekday::Mon,
+        };
+        for part in s.trim().split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part.split_once('=').ok_or_else(|| not_supported(part))?;
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(not_supported(part)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| not_supported(part))?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| not_supported(part))?),
+                "UNTIL" => {
+                    // an UNTIL is in UTC, or a date
+                    let until = parse_date_time(value, Zone::Utc)?;
+                    // an UNTIL date includes the day
+                    rule.until = Some(match value.trim().len() {
+                        8 => until + Duration::days(1) - Duration::seconds(1),
+                        _ => until,
+                    });
+                }
+                "BYDAY" => {
+                    rule.weekdays = value
+                        .split(',')
+                        .map(|day| weekday(day).ok_or_else(|| not_supported(part)))
+                        .collect::<Result<_, _>>()?
+                }
+                "WKST" => rule.week_start = weekday(value).ok_or_else(|| not_supported(part))?,
+                _ => return Err(not_supported(part)),
+            }
+        }
+        rule.frequency =
+            frequency.ok_or_else(|| invalid(format!("The recurrence rule '{s}' needs a FREQ")))?;
+        if !rule.weekdays.is_empty() && rule.frequency != Frequency::Weekly {
+            return Err(not_supported("BYDAY"));
+        }
+        Ok(rule)
+    }
+}
+
+// a weekday of a rule, eg: MO
+fn weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// the days from the start of the week until the weekday
+fn days_into_week(weekday: Weekday, week_start: Weekday) -> i64 {
+    (7 + i64::from(weekday.num_days_from_monday()) - i64::from(week_start.num_days_from_monday()))
+        % 7
+}
+
+impl Rule {
+    // the starts of the occurrences, in order, up to the rule's end or `limit`, from the local start time in the zone
+    fn starts(
+        &self,
+        start: NaiveDateTime,
+        zone: Zone,
+        limit: Option<DateTime<FixedOffset>>,
+    ) -> Result<Vec<DateTime<FixedOffset>>, ProgramIngesterError> {
+        let end = match (self.until, limit) {
+            (Some(until), Some(limit)) => Some(until.min(limit)),
+            (until, limit) => until.or(limit),
+        };
+        let week = start - Duration::days(days_into_week(start.weekday(), self.week_start));
+        let first_of_month = start.with_day(1).unwrap_or(start);
+
+        // the start is always the first occurrence, even when it does not follow the rule
+        let first = zone.resolve(start);
+        if end.is_some_and(|end| first > end) || self.count == Some(0) {
+            return Ok(vec![]);
+        }
+        let mut starts = vec![first];
+        for period in 0..MAX_PERIODS {
+            // periods beyond the dates that can be represented have no occurrences
+            let Some(step) = period.checked_mul(self.interval) else {
+                return Ok(starts);
+            };
+            // the first moment of the period, and its occurrences (dates that do not exist, eg: 30 February, are skipped)
+            let (period_start, candidates) = match self.frequency {
+                Frequency::Daily => {
+                    let Some(day) = start.checked_add_signed(Duration::days(step.into())) else {
+                        return Ok(starts);
+                    };
+                    (day, vec![day])
+                }
+                Frequency::Weekly if self.weekdays.is_empty() => {
+                    let Some(day) = start.checked_add_signed(Duration::weeks(step.into())) else {
+                        return Ok(starts);
+                    };
+                    (day, vec![day])
+                }
+                Frequency::Weekly => {
+                    let Some(week) = week.checked_add_signed(Duration::weeks(step.into())) else {
+                        return Ok(starts);
+                    };
+                    let mut days: Vec<_> = self
+                        .weekdays
+                        .iter()
+                        .filter_map(|weekday| {
+                            week.checked_add_signed(Duration::days(days_into_week(
+                                *weekday,
+                                self.week_start,
+                            )))
+                        })
+                        .collect();
+                    days.sort();
+                    (week, days)
+                }
+                Frequency::Monthly => {
+                    let Some(month) = first_of_month.checked_add_months(Months::new(step)) else {
+                        return Ok(starts);
+                    };
+                    (month, month.with_day(start.day()).into_iter().collect())
+                }
+                Frequency::Yearly => {
+                    let Some(year) =
+                        first_of_month.checked_add_months(Months::new(step.saturating_mul(12)))
+                    else {
+                        return Ok(starts);
+                    };
+                    (year, year.with_day(start.day()).into_iter().collect())
+                }
+            };
+            if end.is_some_and(|end| zone.resolve(period_start) > end) {
+                return Ok(starts);
+            }
+            for candidate in candidates
+                .into_iter()
+                .filter(|candidate| *candidate > start)
+            {
+                let candidate = zone.resolve(candidate);
+                if end.is_some_and(|end| candidate > end)
+                    || self.count.is_some_and(|count| starts.len() >= count)
+                {
+                    return Ok(starts);
+                }
+                starts.push(candidate);
+            }
+        }
+        Err(invalid(format!(
+            "The recurrence rule repeats for more than {MAX_PERIODS} periods"
+        )))
+    }
+}
+
+// a number that changes when the properties do: their 32 bit FNV-1a hash, kept to the non-negative integers
+fn sequence(properties: &[String]) -> u32 {
+    let hash = properties
+        .iter()
+        .flat_map(|property| property.bytes().chain([b'\n']))
+        .fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        });
+    hash & 0x7fff_ffff
+}
+
+// in UTC, eg: 20230101T000000Z
+fn date_time(date: DateTime<FixedOffset>) -> String {
+    date.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+// escape the characters that separate values
+fn text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// split the line into parts of at most 75 bytes (without splitting characters), continued with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for char in line.chars() {
+        if length + char.len_utf8() > LINE_LIMIT {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(char);
+        length += char.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use std::io::BufReader;
+
+    use chrono::DateTime;
+
+    use super::{fold, parse_local, Ics, IcsFormat, Rule, Zone};
+    use crate::{
+        errors::ProgramIngesterError,
+        input::Ingester,
+        output::ProgramGraph,
+        render::{RenderOptions, Renderer, Renderers},
+        source::SourceFormat,
+    };
+
+    #[test]
+    fn test_team_feed() {
+        let input = indoc! {"
+            2023-01-01T00:00:00.000Z 2023-01-11T00:00:00.000Z program1 InProgress TeamA null->Suite
+            2023-01-01T00:00:00.000Z 2023-01-06T00:00:00.000Z program1 Complete TeamB Suite->Email
+            2023-01-11T00:00:00.000Z 2023-01-11T00:00:00.000Z program1 InProgress TeamB Suite->Release
+        "};
+        let ingester = Ingester::try_from(BufReader::new(input.as_bytes()))
+            .expect("test input should be checked");
+        let graph = ProgramGraph::from(ingester.features);
+
+        let options = RenderOptions {
+            team: Some("TeamB".to_string()),
+            ..RenderOptions::default()
+        };
+        let mut feed = vec![];
+        let ics = Ics {
+            stamp: Some(DateTime::parse_from_rfc3339("2023-01-02T12:00:00Z").unwrap()),
+        };
+        ics.render(&graph, &options, &mut feed)
+            .expect("writing to a Vec should work");
+        let feed = String::from_utf8(feed).expect("feed is UTF-8");
+
+        assert_eq!(feed.matches('\n').count(), feed.matches("\r\n").count());
+        assert_eq!(
+            feed.replace("\r\n", "\n"),
+            indoc! {"
+                BEGIN:VCALENDAR
+                VERSION:2.0
+                PRODID:-//program_ingester//EN
+                CALSCALE:GREGORIAN
+                X-WR-CALNAME:TeamB
+                BEGIN:VEVENT
+                UID:program1/Email@program_ingester
+                DTSTAMP:20230102T120000Z
+                SEQUENCE:635468762
+                DTSTART:20230101T000000Z
+                DTEND:20230106T000000Z
+                SUMMARY:Email (TeamB\\, Complete)
+                CATEGORIES:TeamB,Complete
+                DESCRIPTION:program1: Suite/Email
+                X-PROGRAM:program1
+                X-FEATURE:Email
+                X-PARENT:Suite
+                END:VEVENT
+                BEGIN:VEVENT
+                UID:program1/Release@program_ingester
+                DTSTAMP:20230102T120000Z
+                SEQUENCE:1434677141
+                DTSTART:20230111T000000Z
+                SUMMARY:Release (TeamB\\, InProgress)
+                CATEGORIES:TeamB,InProgress
+                DESCRIPTION:program1: Suite/Release
+                X-PROGRAM:program1
+                X-FEATURE:Release
+                X-PARENT:Suite
+                X-MILESTONE:TRUE
+                END:VEVENT
+                END:VCALENDAR
+            "}
+        );
+
+        // the SEQUENCE only changes with the feature
+        let sequences = |graph: &ProgramGraph| {
+            let mut feed = vec![];
+            Ics::default()
+                .render(graph, &options, &mut feed)
+                .expect("writing to a Vec should work");
+            String::from_utf8(feed)
+                .expect("feed is UTF-8")
+                .lines()
+                .filter(|line| line.starts_with("SEQUENCE:"))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sequences(&graph),
+            ["SEQUENCE:635468762", "SEQUENCE:1434677141"]
+        );
+        let mut changed = graph.clone();
+        let email = changed.programs[0]
+            .root
+            .subfeatures
+            .iter_mut()
+            .find(|feature| feature.id == "Email")
+            .expect("Email is a subfeature");
+        email.end_date += chrono::Duration::days(1);
+        assert_eq!(sequences(&changed)[1], "SEQUENCE:1434677141");
+        assert_ne!(sequences(&changed)[0], "SEQUENCE:635468762");
+
+        // only the features without subfeatures
+        let leaves = RenderOptions {
+            leaves: true,
+            ..RenderOptions::default()
+        };
+        let mut feed = vec![];
+        Renderers::new()
+            .by_extension("ics")
+            .expect("ics should be registered")
+            .render(&graph, &leaves, &mut feed)
+            .expect("writing to a Vec should work");
+        let feed = String::from_utf8(feed).expect("feed is UTF-8");
+        assert_eq!(feed.matches("BEGIN:VEVENT").count(), 2);
+        assert!(!feed.contains("UID:program1/Suite@"));
+
+        let long = format!("SUMMARY:{}", "é".repeat(50));
+        let folded = fold(&long);
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), long);
+
+        // the feed reads back as the same features
+        let mut feed = vec![];
+        Ics::default()
+            .render(&graph, &RenderOptions::default(), &mut feed)
+            .expect("writing to a Vec should work");
+        let read = IcsFormat::default()
+            .ingest("plan.ics", Box::new(&feed[..]))
+            .expect("the feed should be read");
+        assert_eq!(ProgramGraph::from(read.features), graph);
+    }
+
+    #[test]
+    fn test_calendar_import() {
+        let calendar = indoc! {"
+            BEGIN:VCALENDAR
+            VERSION:2.0
+            X-WR-CALNAME:program2
+            BEGIN:VTIMEZONE
+            TZID:America/New_York
+            BEGIN:STANDARD
+            DTSTART:19701025T020000
+            END:STANDARD
+            END:VTIMEZONE
+            BEGIN:VEVENT
+            UID:launch
+            SUMMARY:Launch
+            CATEGORIES:TeamA,InProgress
+            DTSTART;VALUE=DATE:20230101
+            DTEND;VALUE=DATE:20230401
+            END:VEVENT
+            BEGIN:VEVENT
+            UID:review
+            SUMMARY:Review
+            X-PARENT:Launch
+            CATEGORIES:TeamB
+            CATEGORIES:NotStarted
+            DTSTART;TZID=America/New_York:20230102T100000
+            DURATION:PT1H
+            RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=5
+            EXDATE;TZID=America/New_York:20230104T100000
+            BEGIN:VALARM
+            ACTION:DISPLAY
+            DESCRIPTION:Reminder
+            TRIGGER:-PT15M
+            END:VALARM
+            END:VEVENT
+            BEGIN:VEVENT
+            UID:review
+            RECURRENCE-ID;TZID=America/New_York:20230109T100000
+            SUMMARY:Review
+            X-PARENT:Launch
+            CATEGORIES:TeamB,Complete
+            DTSTART;TZID=America/New_York:20230110T140000
+            DTEND;TZID=America/New_York:20230110T150000
+            END:VEVENT
+            BEGIN:VEVENT
+            UID:standup
+            SUMMARY:Stand
+             up
+            X-PARENT:Launch
+            CATEGORIES:TeamC,InProgress
+            DTSTART:20230101T090000Z
+            DURATION:PT15M
+            RRULE:FREQ=DAILY;INTERVAL=7
+            END:VEVENT
+            END:VCALENDAR
+        "}
+        .replace('\n', "\r\n");
+        let window = IcsFormat {
+            from: Some(DateTime::parse_from_rfc3339("2023-01-02T00:00:00Z").unwrap()),
+            until: Some(DateTime::parse_from_rfc3339("2023-01-20T00:00:00Z").unwrap()),
+            ..IcsFormat::default()
+        };
+        let features = window
+            .ingest("plan.ics", Box::new(calendar.as_bytes()))
+            .expect("test input should be checked")
+            .features;
+
+        let ids: Vec<&str> = features.iter().map(|feature| feature.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "Launch",
+                "Review@2023-01-02",
+                "Review@2023-01-11",
+                "Review@2023-01-16",
+                "Review@2023-01-09",
+                "Standup@2023-01-08",
+                "Standup@2023-01-15",
+            ]
+        );
+        let [launch, review, .., moved, standup, _] = &features[..] else {
+            panic!("there should be 7 features");
+        };
+        assert_eq!(
+            (launch.program_id.as_str(), launch.parent_id.as_ref()),
+            ("program2", None)
+        );
+        assert_eq!(launch.end_date.to_rfc3339(), "2023-04-01T00:00:00+00:00");
+        assert_eq!(
+            (
+                review.assigned_team.as_str(),
+                review.progress_status.as_str()
+            ),
+            ("TeamB", "NotStarted")
+        );
+        assert_eq!(review.end_date.to_rfc3339(), "2023-01-02T11:00:00-05:00");
+        assert_eq!(moved.progress_status, "Complete");
+        assert_eq!(moved.start_date.to_rfc3339(), "2023-01-10T14:00:00-05:00");
+        assert_eq!(standup.parent_id.as_ref().unwrap(), "Launch");
+        assert_eq!(
+            standup.provenance.as_ref().unwrap().to_string(),
+            "plan.ics:42"
+        );
+        assert_eq!(
+            ProgramGraph::from(features).programs[0]
+                .root
+                .subfeatures
+                .len(),
+            6
+        );
+
+        // a rule without an end needs a window
+        let error = IcsFormat::default()
+            .ingest("plan.ics", Box::new(calendar.as_bytes()))
+            .err()
+            .expect("the stand up repeats forever");
+        assert!(
+            error.to_string().starts_with("plan.ics:42: ")
+                && error.to_string().contains("needs a window"),
+            "{error}"
+        );
+
+        // time zones that are not IANA time zones are not guessed
+        let error = window
+            .ingest(
+                "plan.ics",
+                Box::new(
+                    calendar
+                        .replace("America/New_York", "Eastern Standard Time")
+                        .as_bytes(),
+                ),
+            )
+            .err()
+            .expect("the time zone is not known");
+        assert!(
+            error.to_string().contains("not an IANA time zone"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_recurrence_rules() {
+        let starts = |rule: &str, start: &str| -> Result<Vec<String>, ProgramIngesterError> {
+            let zone = Zone::Named(chrono_tz::America::New_York);
+            let (start, zone) = parse_local(start, zone)?;
+            let starts = rule.parse::<Rule>()?.starts(start, zone, None)?;
+            Ok(starts.iter().map(|start| start.to_rfc3339()).collect())
+        };
+
+        // the start is the first occurrence, even on a day that is not in BYDAY
+        assert_eq!(
+            starts("FREQ=WEEKLY;BYDAY=MO,WE;COUNT=3", "20230103T100000").unwrap(),
+            [
+                "2023-01-03T10:00:00-05:00",
+                "2023-01-04T10:00:00-05:00",
+                "2023-01-09T10:00:00-05:00",
+            ]
+        );
+
+        // the week start changes which weeks are skipped (the examples of RFC 5545)
+        let rule = "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU";
+        let days = |starts: Vec<String>| -> Vec<String> {
+            starts.iter().map(|start| start[..10].to_string()).collect()
+        };
+        assert_eq!(
+            days(starts(&format!("{rule};WKST=MO"), "19970805T090000").unwrap()),
+            ["1997-08-05", "1997-08-10", "1997-08-19", "1997-08-24"]
+        );
+        assert_eq!(
+            days(starts(&format!("{rule};WKST=SU"), "19970805T090000").unwrap()),
+            ["1997-08-05", "1997-08-17", "1997-08-19", "1997-08-31"]
+        );
+
+        // the local time stays the same across a daylight saving change
+        assert_eq!(
+            starts("FREQ=DAILY;COUNT=2", "20230311T100000").unwrap(),
+            ["2023-03-11T10:00:00-05:00", "2023-03-12T10:00:00-04:00"]
+        );
+
+        // intervals that go beyond the dates that can be represented end the occurrences
+        for rule in [
+            "FREQ=DAILY;INTERVAL=100000000;COUNT=3",
+            "FREQ=WEEKLY;INTERVAL=100000000;COUNT=3",
+            "FREQ=WEEKLY;INTERVAL=4294967295;BYDAY=MO;COUNT=3",
+            "FREQ=YEARLY;INTERVAL=4294967295;COUNT=3",
+        ] {
+            assert_eq!(
+                starts(rule, "20230102T100000").unwrap(),
+                ["2023-01-02T10:00:00-05:00"],
+                "{rule}"
+            );
+        }
+
+        // rules that repeat for too long are not cut short
+        assert!(starts("FREQ=DAILY;UNTIL=99991231", "20230101T100000Z").is_err());
+    }
+}
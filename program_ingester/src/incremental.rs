@@ -0,0 +1,435 @@
+//! Build a [ProgramGraph] one [RawFeature] at a time, eg: from lines tailed from a log or a socket.
+//!
+//! Events from distributed systems arrive out of order, so a feature can arrive before its parent.
+//! Such features are kept as pending orphans, and join the graph as soon as their parent (and its ancestors) arrive.
+//! A [snapshot](IncrementalBuilder::snapshot) can be taken at any moment: it holds every feature connected to a root,
+//! exactly as [ProgramGraph::from] would build it from the features received so far.
+//!
+//! Features can also change over time through [Event]s, which are applied in timestamp order.
+//! An event that arrives late (with an earlier timestamp than an event already applied) is reconciled by replaying the history.
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    errors::ProgramIngesterError,
+    events::{Action, Event, EVENT_MARKER},
+    input::{FeatureID, RawFeature},
+    output::{Feature, Program, ProgramGraph},
+};
+
+#[derive(Debug, Default)]
+pub struct IncrementalBuilder {
+    // the latest state of the features
+    state: State,
+
+    // the state before the first event: the features without a timestamp.
+    // It is only kept apart from `state` once there are events, to replay them from
+    base: Option<State>,
+
+    // the events with a timestamp, in timestamp order (and order of arrival for equal timestamps)
+    history: Vec<Event>,
+
+    // the IDs of the features that the events in `history` act on
+    touched: HashSet<FeatureID>,
+}
+
+// the features, and how they are connected
+#[derive(Debug, Default, Clone)]
+struct State {
+    // the features in order of first arrival, a later feature with the same ID replaces the earlier one
+    features: Vec<RawFeature>,
+
+    // the position of each feature ID in `features`
+    index: HashMap<FeatureID, usize>,
+
+    // the child feature IDs of every parent ID, in order of arrival, including parents that have not arrived yet
+    children: HashMap<FeatureID, Vec<FeatureID>>,
+}
+
+impl IncrementalBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a feature, or replace the feature with the same ID (which may move it to another parent).
+    ///
+    /// The feature has no timestamp, so it is part of the initial state (see [Event::create]).
+    pub fn push(&mut self, feature: RawFeature) {
+        self.apply(Event::create(feature));
+    }
+
+    /// Parse a feature or event line (see [RawFeature::from_str] and [Event::from_str]) and add it
+    pub fn push_line(&mut self, line: &str) -> Result<(), ProgramIngesterError> {
+        let line = line.trim_end();
+        match line.starts_with(EVENT_MARKER) {
+            true => self.apply(Event::from_str(line)?),
+            false => self.push(RawFeature::from_str(line)?),
+        }
+        Ok(())
+    }
+
+    /// Apply an event in timestamp order.
+    ///
+    /// Events without a timestamp change the initial state, before every event with a timestamp.
+    /// Events that change a feature which does not exist at their timestamp are ignored.
+    pub fn apply(&mut self, event: Event) {
+        let Some(timestamp) = event.timestamp else {
+            let Some(base) = self.base.as_mut() else {
+                self.state.perform(&event.action);
+                return;
+            };
+            base.perform(&event.action);
+            // the events only need replaying when they act on the same feature
+            match self.touched.contains(event.action.feature_id()) {
+                true => self.replay(),
+                false => self.state.perform(&event.action),
+            }
+            return;
+        };
+
+        if self.base.is_none() {
+            self.base = Some(self.state.clone());
+        }
+        self.touched.insert(event.action.feature_id().clone());
+        let position = self
+            .history
+            .partition_point(|applied| applied.timestamp <= Some(timestamp));
+
+        if position == self.history.len() {
+            self.state.perform(&event.action);
+            self.history.push(event);
+        } else {
+            tracing::debug!(?event.timestamp, "late event, replaying the history");
+            self.history.insert(position, event);
+            self.replay();
+        }
+    }
+
+    /// Every event with a timestamp received, in the order they are applied
+    pub fn history(&self) -> &[Event] {
+        &self.history
+    }
+
+    /// Rebuild the features as they were at `timestamp`, by only applying the events up to (and including) it.
+    ///
+    /// Features without a timestamp are part of every state.
+    pub fn as_of(&self, timestamp: DateTime<FixedOffset>) -> IncrementalBuilder {
+        let mut builder = IncrementalBuilder {
+            state: self.base.as_ref().unwrap_or(&self.state).clone(),
+            ..IncrementalBuilder::default()
+        };
+        for event in self
+            .history
+            .iter()
+            .take_while(|event| event.timestamp.is_none_or(|at| at <= timestamp))
+        {
+            builder.apply(event.clone());
+        }
+        builder
+    }
+
+    /// The latest state of the features, in order of first arrival
+    pub fn into_features(self) -> Vec<RawFeature> {
+        self.state.features
+    }
+
+    /// The number of features received, including pending orphans
+    pub fn len(&self) -> usize {
+        self.state.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.features.is_empty()
+    }
+
+    /// The features that are not connected to a root yet, in order of arrival.
+    ///
+    /// These are the features whose parent (or an ancestor's parent) has not arrived, including features in a cycle.
+    pub fn pending(&self) -> Vec<&RawFeature> {
+        let state = &self.state;
+        let mut connected = vec![false; state.features.len()];
+        let mut stack: Vec<&FeatureID> = state
+            .features
+            .iter()
+            .filter(|feature| feature.is_root())
+            .map(|feature| &feature.id)
+            .collect();
+        while let Some(feature_id) = stack.pop() {
+            connected[state.index[feature_id]] = true;
+            stack.extend(state.children_of(feature_id));
+        }
+
+        state
+            .features
+            .iter()
+            .zip(connected)
+            .filter(|(_, connected)| !connected)
+            .map(|(feature, _)| feature)
+            .collect()
+    }
+
+    /// The graph of every feature connected to a root, with programs and subfeatures in order of arrival
+    pub fn snapshot(&self) -> ProgramGraph {
+        let programs = self
+            .state
+            .features
+            .iter()
+            .filter(|feature| feature.is_root())
+            .map(|root| Program {
+                id: root.program_id.clone(),
+                root: self.state.resolve(root),
+            })
+            .collect();
+
+        let mut graph = ProgramGraph { programs };
+        graph.number_wbs();
+        graph
+    }
+
+    // rebuild the features from the initial state
+    fn replay(&mut self) {
+        let Some(base) = &self.base else {
+            return;
+        };
+        self.state = base.clone();
+        for event in self.history.iter() {
+            self.state.perform(&event.action);
+        }
+    }
+}
+
+impl State {
+    fn resolve(&self, feature: &RawFeature) -> Feature {
+        let subfeatures = self
+            .children_of(&feature.id)
+            .map(|child_id| self.resolve(&self.features[self.index[child_id]]))
+            .collect();
+        Feature::from_raw(feature, subfeatures)
+    }
+
+    fn children_of<'a>(&'a self, feature_id: &FeatureID) -> impl Iterator<Item = &'a FeatureID> {
+        self.children.get(feature_id).into_iter().flatten()
+    }
+
+    fn perform(&mut self, action: &Action) {
+        let existing = |state: &Self, feature_id: &FeatureID| match state.index.get(feature_id) {
+            Some(&position) => Some(state.features[position].clone()),
+            None => {
+                tracing::debug!(
+                    feature_id = feature_id.as_str(),
+                    "no such feature, the event is ignored"
+                );
+                None
+            }
+        };
+
+        match action {
+            Action::Create(feature) => self.upsert(feature.as_ref().clone()),
+            Action::Update { feature_id, fields } => {
+                if let Some(mut feature) = existing(self, feature_id) {
+                    for field in fields {
+                        field.apply(&mut feature);
+                    }
+                    self.upsert(feature);
+                }
+            }
+            Action::Reparent {
+                feature_id,
+                parent_id,
+            } => {
+                if let Some(mut feature) = existing(self, feature_id) {
+                    feature.parent_id = parent_id.clone();
+                    self.upsert(feature);
+                }
+            }
+            Action::Delete { feature_id } => self.remove(feature_id),
+        }
+    }
+
+    fn upsert(&mut self, feature: RawFeature) {
+        match self.index.get(&feature.id) {
+            Some(&position) => {
+                let previous = std::mem::replace(&mut self.features[position], feature);
+                let current = &self.features[position];
+                if previous.parent_id != current.parent_id {
+                    let parent_id = current.parent_id.clone();
+                    if let Some(previous_parent_id) = &previous.parent_id {
+                        self.unlink(previous_parent_id, &previous.id);
+                    }
+                    if let Some(parent_id) = parent_id {
+                        self.link(parent_id, previous.id);
+                    }
+                }
+            }
+            None => {
+                if let Some(parent_id) = &feature.parent_id {
+                    self.link(parent_id.clone(), feature.id.clone());
+                }
+                self.index.insert(feature.id.clone(), self.features.len());
+                self.features.push(feature);
+            }
+        }
+    }
+
+    // the subfeatures stay linked to the removed ID, so they are pending until it is created again
+    fn remove(&mut self, feature_id: &FeatureID) {
+        let Some(position) = self.index.remove(feature_id) else {
+            tracing::debug!(
+                feature_id = feature_id.as_str(),
+                "no such feature, the event is ignored"
+            );
+            return;
+        };
+        let feature = self.features.remove(position);
+        if let Some(parent_id) = &feature.parent_id {
+            self.unlink(parent_id, &feature.id);
+        }
+        for index in self.index.values_mut() {
+            if *index > position {
+                *index -= 1;
+            }
+        }
+    }
+
+    fn link(&mut self, parent_id: FeatureID, child_id: FeatureID) {
+        self.children.entry(parent_id).or_default().push(child_id);
+    }
+
+    fn unlink(&mut self, parent_id: &FeatureID, child_id: &FeatureID) {
+        if let Some(children) = self.children.get_mut(parent_id) {
+            children.retain(|id| id != child_id);
+        }
+    }
+}
+
+impl Extend<RawFeature> for IncrementalBuilder {
+    fn extend<T: IntoIterator<Item = RawFeature>>(&mut self, features: T) {
+        for feature in features {
+            self.push(feature);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::DateTime;
+    use std::str::FromStr;
+
+    use super::IncrementalBuilder;
+    use crate::{input::RawFeature, output::ProgramGraph};
+
+    #[test]
+    fn test_out_of_order_features() {
+        let lines = [
+            "2023-01-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 InProgress TeamB Email->EmailSearch",
+            "2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email",
+            "2023-02-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Chat",
+            "2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite",
+        ];
+
+        let mut builder = IncrementalBuilder::new();
+        for line in &lines[..3] {
+            builder
+                .push_line(line)
+                .expect("test input should be checked");
+        }
+
+        // nothing is connected to a root yet
+        assert_eq!(builder.pending().len(), 3);
+        assert!(builder.snapshot().programs.is_empty());
+
+        builder
+            .push_line(lines[3])
+            .expect("test input should be checked");
+        assert!(builder.pending().is_empty());
+
+        let features: Vec<RawFeature> = lines
+            .iter()
+            .map(|line| RawFeature::from_str(line).expect("test input should be checked"))
+            .collect();
+        assert_eq!(builder.snapshot(), ProgramGraph::from(features));
+
+        // moving EmailSearch under a parent that has not arrived makes it pending again
+        builder
+            .push_line("2023-01-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 InProgress TeamB Search->EmailSearch")
+            .expect("test input should be checked");
+        let pending: Vec<&str> = builder
+            .pending()
+            .iter()
+            .map(|feature| feature.id.as_str())
+            .collect();
+        assert_eq!(pending, vec!["EmailSearch"]);
+        assert_eq!(builder.len(), 4);
+        assert_eq!(builder.snapshot().programs[0].root.flatten().len(), 3);
+    }
+
+    #[test]
+    fn test_events_in_timestamp_order() {
+        let lines = [
+            "2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite",
+            "2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email",
+            "@2023-03-01T00:00:00Z update Email status=Complete team=TeamD",
+            "@2023-04-01T00:00:00Z create 2023-02-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Chat",
+            "@2023-05-01T00:00:00Z delete Chat",
+            // late: it happened before the update above, which must win
+            "@2023-02-01T00:00:00Z update Email status=InProgress team=TeamB end=2023-07-31T00:00:00Z",
+        ];
+
+        let mut builder = IncrementalBuilder::new();
+        for line in lines {
+            builder
+                .push_line(line)
+                .expect("test input should be checked");
+        }
+
+        let graph = builder.snapshot();
+        let root = &graph.programs[0].root;
+        assert_eq!(root.subfeatures.len(), 1);
+        let email = &root.subfeatures[0];
+        assert_eq!(email.progress_status, "Complete");
+        assert_eq!(email.assigned_team, "TeamD");
+        assert_eq!(email.end_date.to_rfc3339(), "2023-07-31T00:00:00+00:00");
+        // the features without a timestamp are not events
+        assert_eq!(builder.history().len(), 4);
+
+        // before the updates, and before Chat was created
+        let timestamp = DateTime::parse_from_rfc3339("2023-02-15T00:00:00Z")
+            .expect("test dates should be checked");
+        let graph = builder.as_of(timestamp).snapshot();
+        let email = &graph.programs[0].root.subfeatures[0];
+        assert_eq!(graph.programs[0].root.subfeatures.len(), 1);
+        assert_eq!(email.progress_status, "InProgress");
+        assert_eq!(email.end_date.to_rfc3339(), "2023-07-31T00:00:00+00:00");
+
+        // Chat existed for a month
+        let timestamp = DateTime::parse_from_rfc3339("2023-04-15T00:00:00Z")
+            .expect("test dates should be checked");
+        assert_eq!(
+            builder.as_of(timestamp).snapshot().programs[0]
+                .root
+                .subfeatures
+                .len(),
+            2
+        );
+
+        // a feature without a timestamp is part of the initial state, even when it arrives after its events
+        builder
+            .push_line("@2023-03-01T00:00:00Z update Calendar status=Complete")
+            .expect("test input should be checked");
+        builder
+            .push_line("2023-01-01T00:00:00.000Z 2023-02-28T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Calendar")
+            .expect("test input should be checked");
+        let graph = builder.snapshot();
+        let calendar = graph.programs[0]
+            .root
+            .subfeatures
+            .iter()
+            .find(|feature| feature.id == "Calendar")
+            .expect("Calendar is a subfeature");
+        assert_eq!(calendar.progress_status, "Complete");
+    }
+}
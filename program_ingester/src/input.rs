@@ -1,13 +1,24 @@
 /// A crate for defining the input structs and implement their traits
 use std::{
-    collections::HashMap,
-    io::{BufRead, BufReader, Read},
+    borrow::BorrowMut,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
-use chrono::{DateTime, FixedOffset};
+use chrono::FixedOffset;
+use serde::{Deserialize, Serialize};
 
-use crate::errors::ProgramIngesterError;
+use crate::{
+    borrowed::RawFeatureRef,
+    errors::ProgramIngesterError,
+    events::{Event, EVENT_MARKER},
+    incremental::IncrementalBuilder,
+    symbol::{Interner, Symbol},
+};
 
 /// This code defines a struct named Ingester and its implementation of the TryFrom trait.
 /// The struct Ingester has one public field, features, which is a vector of RawFeatures.
@@ -19,33 +30,284 @@ use crate::errors::ProgramIngesterError;
 /// Finally, the implementation returns an Ok variant of a Result containing the Ingester struct, with its features field populated with the RawFeatures created from the input data.
 /// If any errors occur during the reading or conversion process, the implementation returns an Err variant of the Result,
 /// with the error being of type crate::errors::ProgramIngesterError.
-
+///
 /// The main entrypoint
 pub struct Ingester {
     pub features: Vec<RawFeature>,
+
+    /// The event lines (starting with [EVENT_MARKER]), see [crate::events]
+    pub events: Vec<Event>,
 }
 
-// // Todo: make this work to produce a vec of features from a reader (eg: file, stdin, etc...)
+/// The source name used for readers that are not named, see [Ingester::from_source]
+pub const UNNAMED_SOURCE: &str = "input";
+
 impl<R: Read> TryFrom<BufReader<R>> for Ingester {
     type Error = crate::errors::ProgramIngesterError;
 
-    fn try_from(mut reader: BufReader<R>) -> Result<Self, Self::Error> {
+    fn try_from(reader: BufReader<R>) -> Result<Self, Self::Error> {
+        Ingester::from_source(UNNAMED_SOURCE, reader)
+    }
+}
+
+impl Ingester {
+    /// Read features from a reader, recording the source name and line number of each one.
+    ///
+    /// Errors in a line are wrapped in [ProgramIngesterError::Located], eg: `plan.txt:12: The program input is not valid: ...`
+    pub fn from_source<R: Read>(
+        name: impl Into<String>,
+        reader: BufReader<R>,
+    ) -> Result<Self, ProgramIngesterError> {
+        Ingester::read(name.into(), reader, &mut Interner::new())
+    }
+
+    // read a source, sharing the names of its features with the interner
+    fn read<R: Read>(
+        name: String,
+        reader: BufReader<R>,
+        interner: &mut Interner,
+    ) -> Result<Self, ProgramIngesterError> {
         let mut features = vec![];
-        let mut buf = String::new();
+        let mut events = vec![];
+        for line in Lines::new(name, reader, interner) {
+            match line? {
+                Line::Feature(feature) => features.push(feature),
+                Line::Event(event) => events.push(event),
+            }
+        }
+        Ok(Ingester { features, events })
+    }
 
-        // It's more efficient to allocate a single string buffer and loop
-        // over reader.read_line(), rather than using reader.lines().map()
-        // which will allocate a new String on each iteration
-        while reader.read_line(&mut buf)? > 0 {
-            {
-                // remove the trailing \n
-                let line = buf.trim_end();
-                let feature = RawFeature::from_str(line)?;
-                features.push(feature);
+    /// Read and merge the features of several inputs, in order. See [expand_paths] for the inputs that are accepted.
+    ///
+    /// Parent references are resolved when the [ProgramGraph](crate::output::ProgramGraph) is built,
+    /// so a feature can have its parent in another file.
+    pub fn from_paths<P: AsRef<Path>>(inputs: &[P]) -> Result<Self, ProgramIngesterError> {
+        let mut features = vec![];
+        let mut events = vec![];
+        // the files share their names, eg: the teams
+        let mut interner = Interner::new();
+        for path in expand_paths(inputs)? {
+            let name = path.display().to_string();
+            let file = File::open(&path).map_err(|error| ProgramIngesterError::Located {
+                location: name.clone(),
+                error: Box::new(error.into()),
+            })?;
+            let ingester = Ingester::read(name, BufReader::new(file), &mut interner)?;
+            features.extend(ingester.features);
+            events.extend(ingester.events);
+        }
+        Ok(Ingester { features, events })
+    }
+
+    /// The latest state of the features.
+    ///
+    /// Without events, these are the features as they were read. Otherwise, the features are the initial state,
+    /// and the events are applied to them in timestamp order (see [IncrementalBuilder::apply]).
+    pub fn into_features(self) -> Vec<RawFeature> {
+        if self.events.is_empty() {
+            return self.features;
+        }
+        self.into_builder().into_features()
+    }
+
+    /// Hold the features and events in a builder, eg: to look at them as of an earlier time with [IncrementalBuilder::as_of]
+    pub fn into_builder(mut self) -> IncrementalBuilder {
+        let mut builder = IncrementalBuilder::new();
+        builder.extend(self.features);
+        // in timestamp order, so no event arrives late and replays the history (the sort is stable, so ties keep their order)
+        self.events.sort_by_key(|event| event.timestamp);
+        for event in self.events {
+            builder.apply(event);
+        }
+        builder
+    }
+}
+
+/// The lines of an input, parsed one at a time, with where each one was read from.
+///
+/// Lines that are not UTF-8 are [ProgramIngesterError::Located] like the other problems. It stops after the first error.
+pub(crate) struct Lines<R, I> {
+    name: String,
+    reader: R,
+    interner: I,
+    line: usize,
+    offset: usize,
+    // It's more efficient to allocate a single buffer and loop over
+    // reader.read_until(), rather than using reader.lines().map()
+    // which will allocate a new String on each iteration
+    buf: Vec<u8>,
+    failed: bool,
+}
+
+impl<R: BufRead, I: BorrowMut<Interner>> Lines<R, I> {
+    /// Read the lines of `reader`, sharing the names of their features with the interner
+    pub(crate) fn new(name: impl Into<String>, reader: R, interner: I) -> Self {
+        Lines {
+            name: name.into(),
+            reader,
+            interner,
+            line: 0,
+            offset: 0,
+            buf: vec![],
+            failed: false,
+        }
+    }
+
+    fn read_line(&mut self) -> Option<Result<Line, ProgramIngesterError>> {
+        self.buf.clear();
+        let read = match self.reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => return None,
+            Ok(read) => read,
+            Err(error) => return Some(Err(error.into())),
+        };
+        self.line += 1;
+        let provenance = Provenance {
+            source: self.name.clone(),
+            line: self.line,
+            offset: self.offset,
+        };
+        self.offset += read;
+
+        let text = match std::str::from_utf8(&self.buf) {
+            Ok(text) => text,
+            Err(error) => {
+                return Some(Err(ProgramIngesterError::Located {
+                    location: provenance.to_string(),
+                    error: Box::new(ProgramIngesterError::InvalidProgramInput(format!(
+                        "The line is not UTF-8: {error}"
+                    ))),
+                }))
+            }
+        };
+        // remove the trailing \n
+        Some(Line::parse_with(
+            text.trim_end(),
+            provenance,
+            self.interner.borrow_mut(),
+        ))
+    }
+}
+
+impl<R: BufRead, I: BorrowMut<Interner>> Iterator for Lines<R, I> {
+    type Item = Result<Line, ProgramIngesterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let line = self.read_line();
+        self.failed = matches!(line, Some(Err(_)));
+        line
+    }
+}
+
+/// A line of the input: a feature, or an event (starting with [EVENT_MARKER])
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    Feature(RawFeature),
+    Event(Event),
+}
+
+impl Line {
+    /// Parse a line (without its trailing newline), recording where it was read from.
+    ///
+    /// Errors are wrapped in [ProgramIngesterError::Located].
+    pub fn parse(text: &str, provenance: Provenance) -> Result<Self, ProgramIngesterError> {
+        Line::parse_with(text, provenance, &mut Interner::new())
+    }
+
+    /// Like [Line::parse], sharing the names (eg: the teams) with the other lines of the interner
+    pub fn parse_with(
+        text: &str,
+        provenance: Provenance,
+        interner: &mut Interner,
+    ) -> Result<Self, ProgramIngesterError> {
+        let located = |error| ProgramIngesterError::Located {
+            location: provenance.to_string(),
+            error: Box::new(error),
+        };
+
+        if text.starts_with(EVENT_MARKER) {
+            let mut event = Event::from_str(text).map_err(located)?;
+            event.intern(interner);
+            Ok(Line::Event(event.with_provenance(provenance)))
+        } else {
+            let mut feature = RawFeatureRef::parse(text)
+                .map_err(located)?
+                .intern(interner);
+            feature.provenance = Some(provenance);
+            Ok(Line::Feature(feature))
+        }
+    }
+
+    /// Share the names of the line with the other lines of the interner, eg: lines parsed with another interner
+    pub fn intern(&mut self, interner: &mut Interner) {
+        match self {
+            Line::Feature(feature) => feature.intern(interner),
+            Line::Event(event) => event.intern(interner),
+        }
+    }
+}
+
+/// Turn inputs into the list of files to read, in order, without repeats:
+/// - a directory is replaced by the files directly in it, sorted by name
+/// - a path that does not exist, and contains `*`, `?` or `[`, is a glob pattern, replaced by its matching files sorted by name
+/// - anything else is kept as is
+pub fn expand_paths<P: AsRef<Path>>(inputs: &[P]) -> Result<Vec<PathBuf>, ProgramIngesterError> {
+    let mut paths = vec![];
+    for input in inputs.iter().map(AsRef::as_ref) {
+        if input.is_dir() {
+            let mut files = fs::read_dir(input)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            files.retain(|path| path.is_file());
+            files.sort();
+            paths.extend(files);
+        } else if !input.exists() && input.to_string_lossy().contains(['*', '?', '[']) {
+            let pattern = input.to_string_lossy();
+            let mut files = glob::glob(&pattern)
+                .map_err(|error| {
+                    ProgramIngesterError::InvalidProgramInput(format!(
+                        "The pattern '{pattern}' is not valid: {error}"
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(io::Error::from)?;
+            files.retain(|path| path.is_file());
+            if files.is_empty() {
+                return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                    "The pattern '{pattern}' does not match any files"
+                )));
             }
-            buf.clear();
+            files.sort();
+            paths.extend(files);
+        } else {
+            paths.push(input.to_path_buf());
         }
-        Ok(Ingester { features })
+    }
+
+    let mut seen = HashSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+    Ok(paths)
+}
+
+/// Where a feature was read from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// The name of the source, eg: the file path
+    pub source: String,
+
+    /// The line number, starting at 1
+    pub line: usize,
+
+    /// The byte offset of the start of the line, from the start of the source
+    pub offset: usize,
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.source, self.line)
     }
 }
 
@@ -57,24 +319,24 @@ impl<R: Read> TryFrom<BufReader<R>> for Ingester {
 /// into its component parts. If the string slice doesn't have the expected format or can't be parsed into a RawFeature,
 /// then the implementation returns an error.
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RawFeature {
     /// This node's ID
-    pub id: String,
+    pub id: FeatureID,
 
     /// The parent feature
     ///
     /// If it is set to `None`, then this is a root feature
-    pub parent_id: Option<String>,
+    pub parent_id: Option<FeatureID>,
 
     /// Program ID
-    pub program_id: String,
+    pub program_id: Symbol,
 
     /// progress_status (Complete, In Progress)
-    pub progress_status: String,
+    pub progress_status: Symbol,
 
     /// The name of the assigned team generating the feature
-    pub assigned_team: String,
+    pub assigned_team: Symbol,
 
     /// The Feature Start Time
     pub start_date: chrono::DateTime<FixedOffset>,
@@ -82,12 +344,114 @@ pub struct RawFeature {
     /// Feature End Time
     ///
     pub end_date: chrono::DateTime<FixedOffset>,
+
+    /// Whether this is a regular feature or a milestone
+    pub kind: FeatureKind,
+
+    /// How much of the work is done, from 0 to 100
+    ///
+    /// If it is set to `None`, it is derived from the progress_status
+    pub percent_complete: Option<u8>,
+
+    /// The planned cost (or effort) of the work
+    pub planned_cost: Option<f64>,
+
+    /// The actual cost (or effort) spent so far
+    pub actual_cost: Option<f64>,
+
+    /// Optimistic, most likely and pessimistic durations, for schedule risk simulation
+    pub estimate: Option<ThreePointEstimate>,
+
+    /// Where the feature was read from
+    ///
+    /// It is set to `None` when the feature was parsed directly from a string
+    pub provenance: Option<Provenance>,
 }
 
 impl RawFeature {
     pub fn is_root(&self) -> bool {
         self.parent_id.is_none()
     }
+
+    pub fn is_milestone(&self) -> bool {
+        self.kind == FeatureKind::Milestone
+    }
+
+    /// Share the names of the feature with the other features of the interner
+    pub fn intern(&mut self, interner: &mut Interner) {
+        interner.share(&mut self.id);
+        if let Some(parent_id) = &mut self.parent_id {
+            interner.share(parent_id);
+        }
+        interner.share(&mut self.program_id);
+        interner.share(&mut self.progress_status);
+        interner.share(&mut self.assigned_team);
+    }
+}
+
+/// The progress_status of features whose work is done
+pub const COMPLETE_STATUS: &str = "Complete";
+
+/// The progress_status of features whose work has started
+pub const IN_PROGRESS_STATUS: &str = "InProgress";
+
+/// The marker that can be appended to a feature line to flag it as a milestone
+pub const MILESTONE_MARKER: &str = "milestone";
+
+/// FeatureKind tells a regular feature (drawn as a bar) apart from a milestone (drawn as a diamond).
+/// A feature is a milestone when its line carries the [MILESTONE_MARKER], or when its start_date equals its end_date.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureKind {
+    #[default]
+    Task,
+    Milestone,
+}
+
+/// The longest duration an estimate can have, in days (about 1000 years)
+pub const MAX_ESTIMATE_DAYS: f64 = 365_250.0;
+
+/// A three-point estimate of a feature's duration, in days
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThreePointEstimate {
+    pub optimistic: f64,
+    pub most_likely: f64,
+    pub pessimistic: f64,
+}
+
+impl FromStr for ThreePointEstimate {
+    type Err = crate::errors::ProgramIngesterError;
+
+    /// Parse `optimistic/most_likely/pessimistic`, eg: `5/8/15`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let days: Vec<f64> = s
+            .split('/')
+            .map(|part| part.parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| {
+                ProgramIngesterError::InvalidProgramInput(format!(
+                    "The estimate '{s}' needs to be numbers of days"
+                ))
+            })?;
+
+        match days[..] {
+            [optimistic, most_likely, pessimistic]
+                if 0.0 <= optimistic
+                    && optimistic <= most_likely
+                    && most_likely <= pessimistic
+                    && pessimistic <= MAX_ESTIMATE_DAYS =>
+            {
+                Ok(ThreePointEstimate {
+                    optimistic,
+                    most_likely,
+                    pessimistic,
+                })
+            }
+            _ => Err(ProgramIngesterError::InvalidProgramInput(format!(
+                "The estimate '{s}' needs to be 3 parts, optimistic/most_likely/pessimistic, in increasing order, of at most {MAX_ESTIMATE_DAYS} days"
+            ))),
+        }
+    }
 }
 
 impl TryFrom<String> for RawFeature {
@@ -104,39 +468,37 @@ impl FromStr for RawFeature {
     /// Try to turn a program log line into a feature
     ///
     /// Example: `2016-10-20T12:43:34.000Z 2016-10-20T12:43:35.000Z program1 back-end-3 ac->ad`
+    ///
+    /// The 6 required parts can be followed by optional attributes:
+    /// - `milestone` marks the feature as a milestone
+    /// - `percent=40` sets the percent complete (0 to 100)
+    /// - `cost=120.5` sets the planned cost (or effort)
+    /// - `actual=80` sets the actual cost spent so far
+    /// - `estimate=5/8/15` sets the optimistic/most likely/pessimistic duration in days
+    ///
+    /// Example: `2016-10-20T00:00:00.000Z 2016-10-30T00:00:00.000Z program1 InProgress back-end-3 ac->ae percent=40 cost=100`
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.trim().split(" ").collect();
-        // We should have 6 parts.
-        if parts.len() != 6 {
-            return Err(ProgramIngesterError::InvalidProgramInput(format!(
-                "The feature '{s}' needs to have 6 parts, start, end, program, progress_status, assigned_team, feature-relation"
-            )));
-        }
-
-        // If so, then we need to split the last one part
-        let last_part = parts
-            .last()
-            .expect("we checked that there are 6 parts earlier");
-        let feature_ids: Vec<&str> = last_part.split("->").collect();
+        Ok(RawFeatureRef::parse(s)?.into_owned())
+    }
+}
 
-        if feature_ids.len() != 2 {
-            return Err(ProgramIngesterError::InvalidProgramInput(format!(
-                "The feature-relation '{s}' needs to have 2 parts"
-            )));
-        }
+// percentages must be whole numbers from 0 to 100
+pub(crate) fn parse_percent(value: &str, line: &str) -> Result<u8, ProgramIngesterError> {
+    match value.parse::<u8>() {
+        Ok(percent) if percent <= 100 => Ok(percent),
+        _ => Err(ProgramIngesterError::InvalidProgramInput(format!(
+            "The percent complete '{value}' in '{line}' needs to be a whole number from 0 to 100"
+        ))),
+    }
+}
 
-        Ok(RawFeature {
-            id: feature_ids.last().unwrap().to_owned().into(),
-            parent_id: match feature_ids.first().unwrap().to_owned() {
-                "null" => None,
-                id => Some(id.into()),
-            },
-            start_date: DateTime::parse_from_rfc3339(parts.first().unwrap().to_owned())?,
-            end_date: DateTime::parse_from_rfc3339(parts.get(1).unwrap().to_owned())?,
-            program_id: parts.get(2).unwrap().to_owned().into(),
-            progress_status: parts.get(3).unwrap().to_owned().into(),
-            assigned_team: parts.get(4).unwrap().to_owned().into(),
-        })
+// costs must be finite and not negative
+pub(crate) fn parse_cost(value: &str, line: &str) -> Result<f64, ProgramIngesterError> {
+    match value.parse::<f64>() {
+        Ok(cost) if cost.is_finite() && cost >= 0.0 => Ok(cost),
+        _ => Err(ProgramIngesterError::InvalidProgramInput(format!(
+            "The cost '{value}' in '{line}' needs to be a number of 0 or more"
+        ))),
     }
 }
 
@@ -148,7 +510,7 @@ impl FromStr for RawFeature {
 /// IDs that can be used to look up related information in the FeatureMap.
 /// FeatureMap, is a type alias for a HashMap (hash map) data structure.
 /// A HashMap is a collection of key-value pairs, where the keys are of type FeatureID
-/// (which is defined as a type alias for String) and the values are of type FeatureDataAndChildren.
+/// (which is defined as a type alias for [Symbol], a shared string) and the values are of type FeatureDataAndChildren.
 /// This type alias makes it more expressive and easier to use, as the type FeatureMap is more
 /// meaningful and understandable than the underlying type HashMap.
 /// 'a is a lifetime annotation. In Rust, lifetimes are a way of expressing the relationship between references.
@@ -162,16 +524,27 @@ pub struct FeatureDataAndChildren<'a> {
     // These are the child feature IDs which can then be looked up in the FeatureMap (HashMap)
     pub children: Vec<FeatureID>,
 }
+
+impl<'a> FeatureDataAndChildren<'a> {
+    /// Where the feature was read from, if it has been ingested and it is known
+    pub fn provenance(&self) -> Option<&'a Provenance> {
+        self.feature_data
+            .and_then(|feature_data| feature_data.provenance.as_ref())
+    }
+}
+
 // alias types to make usage simpler and more expressive
-pub type FeatureID = String;
+pub type FeatureID = Symbol;
 pub type FeatureMap<'a> = HashMap<FeatureID, FeatureDataAndChildren<'a>>;
 
 #[cfg(test)]
 mod test {
     use chrono::DateTime;
-    use std::str::FromStr;
+    use indoc::indoc;
+    use std::{io::BufReader, str::FromStr};
 
-    use super::RawFeature;
+    use super::{FeatureKind, Ingester, Provenance, RawFeature, ThreePointEstimate};
+    use crate::output::ProgramGraph;
 
     #[test]
     fn test_parsing_single_program() {
@@ -187,6 +560,12 @@ mod test {
                 .expect("test dates should be checked"),
             end_date: DateTime::parse_from_rfc3339("2023-06-30T00:00:00.000Z")
                 .expect("test dates should be checked"),
+            kind: FeatureKind::Task,
+            percent_complete: None,
+            planned_cost: None,
+            actual_cost: None,
+            estimate: None,
+            provenance: None,
         };
 
         let actual = RawFeature::from_str(input);
@@ -195,4 +574,102 @@ mod test {
             assert_eq!(parsed, expected);
         }
     }
+
+    #[test]
+    fn test_parsing_milestones() {
+        let marked = "2023-06-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Review milestone";
+        let zero_length = "2023-06-30T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Release";
+
+        for input in [marked, zero_length] {
+            let parsed = RawFeature::from_str(input).expect("milestone should parse");
+            assert_eq!(parsed.kind, FeatureKind::Milestone);
+        }
+
+        let unknown_marker = "2023-06-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Review diamond";
+        assert!(RawFeature::from_str(unknown_marker).is_err());
+    }
+
+    #[test]
+    fn test_parsing_progress_and_cost() {
+        let input = "2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email percent=40 cost=1200.5 actual=600";
+
+        let parsed = RawFeature::from_str(input).expect("attributes should parse");
+        assert_eq!(parsed.percent_complete, Some(40));
+        assert_eq!(parsed.planned_cost, Some(1200.5));
+        assert_eq!(parsed.actual_cost, Some(600.0));
+
+        let over_complete = "2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email percent=140";
+        assert!(RawFeature::from_str(over_complete).is_err());
+
+        // nothing spent yet is a cost, less than nothing is not
+        let unspent = "2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email actual=0";
+        assert_eq!(
+            RawFeature::from_str(unspent).unwrap().actual_cost,
+            Some(0.0)
+        );
+        let refund = "2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email actual=-5";
+        let error = RawFeature::from_str(refund).expect_err("negative costs are not valid");
+        assert!(error.to_string().contains("The cost '-5' in '"), "{error}");
+        assert!(
+            error
+                .to_string()
+                .ends_with("needs to be a number of 0 or more"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_parsing_estimates() {
+        let input = "2023-01-01T00:00:00.000Z 2023-01-09T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email estimate=5/8/15.5";
+
+        let parsed = RawFeature::from_str(input).expect("estimate should parse");
+        assert_eq!(
+            parsed.estimate,
+            Some(ThreePointEstimate {
+                optimistic: 5.0,
+                most_likely: 8.0,
+                pessimistic: 15.5,
+            })
+        );
+
+        assert!(ThreePointEstimate::from_str("8/5/15").is_err());
+        assert!(ThreePointEstimate::from_str("5/8").is_err());
+        // too long to simulate
+        assert!(ThreePointEstimate::from_str("1/2/1000000000").is_err());
+    }
+
+    #[test]
+    fn test_provenance() {
+        let input = indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email
+        "};
+        let ingester = Ingester::from_source("plan.txt", BufReader::new(input.as_bytes()))
+            .expect("test input should be checked");
+
+        let email = Provenance {
+            source: "plan.txt".into(),
+            line: 2,
+            offset: input.find('\n').expect("there are 2 lines") + 1,
+        };
+        assert_eq!(ingester.features[1].provenance, Some(email.clone()));
+        assert_eq!(email.to_string(), "plan.txt:2");
+
+        // it flows into the graph, and into its JSON when it is asked for
+        let graph = ProgramGraph::from(ingester.features);
+        let root = &graph.programs[0].root;
+        assert_eq!(root.subfeatures[0].provenance, Some(email));
+
+        let json = serde_json::to_value(&graph).expect("graph should serialise");
+        assert!(json["programs"][0]["root"].get("provenance").is_none());
+        let json = serde_json::to_value(graph.with_provenance()).expect("graph should serialise");
+        assert_eq!(json["programs"][0]["root"]["provenance"]["offset"], 0);
+        assert_eq!(
+            json["programs"][0]["root"]["subfeatures"][0]["provenance"]["line"],
+            2
+        );
+        let json = serde_json::to_value(graph.without_provenance().with_provenance())
+            .expect("graph should serialise");
+        assert!(json["programs"][0]["root"].get("provenance").is_none());
+    }
 }
@@ -25,9 +25,31 @@
 //! ```
 
 /// docuemtning
+#[cfg(feature = "async")]
+pub mod async_input;
+pub mod baseline;
+pub mod borrowed;
+pub mod delta;
+pub mod earned_value;
 pub mod errors;
+pub mod events;
+pub mod health;
+pub mod ics;
+pub mod incremental;
 pub mod input;
+pub mod mspdi;
 pub mod output;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod render;
+pub mod simulation;
+pub mod source;
+pub mod stats;
+pub mod symbol;
+#[cfg(test)]
+mod testing;
+pub mod validate;
+pub mod wbs;
 
 #[cfg(test)]
 mod test {
@@ -36,7 +58,7 @@ mod test {
     use std::io::BufReader;
 
     use crate::{
-        input::Ingester,
+        input::{FeatureKind, Ingester},
         output::{Feature, Program, ProgramGraph},
     };
 
@@ -54,39 +76,67 @@ mod test {
             id: "program1".into(),
             root: Feature {
                 id: "ProductivitySuite".into(),
+                wbs: None,
                 progress_status: "InProgress".into(),
                 assigned_team: "TeamA".into(),
                 start_date: DateTime::parse_from_rfc3339("2023-01-01T00:00:00.000Z")
                     .expect("test dates should be checked"),
                 end_date: DateTime::parse_from_rfc3339("2023-12-31T00:00:00.000Z")
                     .expect("test dates should be checked"),
+                kind: FeatureKind::Task,
+                percent_complete: None,
+                planned_cost: None,
+                actual_cost: None,
+                estimate: None,
+                provenance: None,
                 subfeatures: vec![Feature {
                     id: "Email".into(),
+                    wbs: None,
                     progress_status: "Complete".into(),
                     assigned_team: "TeamB".into(),
                     start_date: DateTime::parse_from_rfc3339("2023-01-01T00:00:00.000Z")
                         .expect("test dates should be checked"),
                     end_date: DateTime::parse_from_rfc3339("2023-06-30T00:00:00.000Z")
                         .expect("test dates should be checked"),
+                    kind: FeatureKind::Task,
+                    percent_complete: None,
+                    planned_cost: None,
+                    actual_cost: None,
+                    estimate: None,
+                    provenance: None,
                     subfeatures: vec![
                         Feature {
                             id: "EmailSearch".into(),
+                            wbs: None,
                             progress_status: "Complete".into(),
                             assigned_team: "TeamB".into(),
                             start_date: DateTime::parse_from_rfc3339("2023-01-01T00:00:00.000Z")
                                 .expect("test dates should be checked"),
                             end_date: DateTime::parse_from_rfc3339("2023-04-30T00:00:00.000Z")
                                 .expect("test dates should be checked"),
+                            kind: FeatureKind::Task,
+                            percent_complete: None,
+                            planned_cost: None,
+                            actual_cost: None,
+                            estimate: None,
+                            provenance: None,
                             subfeatures: vec![],
                         },
                         Feature {
                             id: "EmailFilters".into(),
+                            wbs: None,
                             progress_status: "Complete".into(),
                             assigned_team: "TeamB".into(),
                             start_date: DateTime::parse_from_rfc3339("2023-05-01T00:00:00.000Z")
                                 .expect("test dates should be checked"),
                             end_date: DateTime::parse_from_rfc3339("2023-06-30T00:00:00.000Z")
                                 .expect("test dates should be checked"),
+                            kind: FeatureKind::Task,
+                            percent_complete: None,
+                            planned_cost: None,
+                            actual_cost: None,
+                            estimate: None,
+                            provenance: None,
                             subfeatures: vec![],
                         },
                     ],
@@ -102,7 +152,7 @@ mod test {
             let programgraph = ProgramGraph::from(ingester.features);
 
             assert_eq!(programgraph.programs.len(), 1);
-            assert_eq!(programgraph.programs.get(0), Some(&expected));
+            assert_eq!(programgraph.programs.first(), Some(&expected));
         }
     }
 }
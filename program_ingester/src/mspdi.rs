@@ -0,0 +1,544 @@
+//! Microsoft Project XML (MSPDI) schedules, eg: the exports of a PMO's plans.
+//!
+//! Every `Task` of the project is a feature of one program, named after the project. The tasks are in outline order,
+//! so the parent of a task is the task before it one `OutlineLevel` up (or one `OutlineNumber` part shorter),
+//! and the project's summary task (level 0) is the root. Exports without a summary task get a root named after the
+//! project, spanning its top level tasks (and given a UID, eg: `Suite#0`, when a task has the project's name). `Start` and `Finish` are the dates, read as UTC (see [MspdiFormat]).
+//!
+//! The names of the resources assigned to a task are its team, and its `PercentComplete` is its progress
+//! and status: `NotStarted`, `InProgress` or `Complete`. Features have no dependencies, so `PredecessorLink`s are not kept.
+use std::{
+    collections::{HashMap, HashSet},
+    io::BufRead,
+    path::Path,
+};
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use roxmltree::{Document, Node};
+
+use crate::{
+    errors::ProgramIngesterError,
+    input::{FeatureID, FeatureKind, Provenance, RawFeature, COMPLETE_STATUS, IN_PROGRESS_STATUS},
+    source::{FeatureSource, SourceFormat},
+    symbol::Interner,
+};
+
+/// The status of tasks without any work done
+pub const NOT_STARTED_STATUS: &str = "NotStarted";
+
+/// The team of tasks without resources
+pub const UNASSIGNED_TEAM: &str = "Unassigned";
+
+/// A Microsoft Project XML export, a feature per task
+///
+/// Task names are the feature IDs, and names that several tasks have get the task's UID, eg: `Review#12`.
+///
+/// MSPDI dates are local times without an offset, and the export does not say which time zone its calendars are in,
+/// so they are read as UTC: a task starting at 08:00 in New York starts at `08:00:00+00:00`.
+/// Convert the dates before comparing them with sources that have offsets.
+pub struct MspdiFormat;
+
+impl SourceFormat for MspdiFormat {
+    fn name(&self) -> &str {
+        "mspdi"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xml", "mspdi"]
+    }
+
+    fn features<'a>(
+        &self,
+        name: &str,
+        mut reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn FeatureSource + 'a> {
+        let mut text = String::new();
+        let features = reader
+            .read_to_string(&mut text)
+            .map_err(ProgramIngesterError::from)
+            .and_then(|_| Document::parse(&text).map_err(ProgramIngesterError::from))
+            .map_err(|error| ProgramIngesterError::Located {
+                location: name.into(),
+                error: Box::new(error),
+            })
+            .and_then(|document| read(name, &document));
+        match features {
+            Ok(features) => Box::new(features.into_iter().map(Ok)),
+            Err(error) => Box::new(std::iter::once(Err(error))),
+        }
+    }
+}
+
+fn read(name: &str, document: &Document) -> Result<Vec<RawFeature>, ProgramIngesterError> {
+    let project = document.root_element();
+    if project.tag_name().name() != "Project" {
+        return Err(ProgramIngesterError::Located {
+            location: name.into(),
+            error: Box::new(ProgramIngesterError::InvalidProgramInput(format!(
+                "The XML needs to be a Microsoft Project <Project>, not <{}>",
+                project.tag_name().name()
+            ))),
+        });
+    }
+    let mut interner = Interner::new();
+    let program_id = interner.intern(
+        text(project, "Name")
+            .or_else(|| text(project, "Title"))
+            .or_else(|| Path::new(name).file_stem().and_then(|stem| stem.to_str()))
+            .unwrap_or(name),
+    );
+
+    let resources: HashMap<&str, &str> = elements(project, "Resources")
+        .flat_map(|resources| elements(resources, "Resource"))
+        .filter_map(|resource| Some((text(resource, "UID")?, text(resource, "Name")?)))
+        .collect();
+    // the names of the resources of each task, by the task's UID
+    let mut teams: HashMap<&str, Vec<&str>> = HashMap::new();
+    for assignment in
+        elements(project, "Assignments").flat_map(|assignments| elements(assignments, "Assignment"))
+    {
+        let resource = text(assignment, "ResourceUID").and_then(|uid| resources.get(uid));
+        if let (Some(task_uid), Some(resource)) = (text(assignment, "TaskUID"), resource) {
+            teams.entry(task_uid).or_default().push(resource);
+        }
+    }
+
+    let tasks: Vec<Node> = elements(project, "Tasks")
+        .flat_map(|tasks| elements(tasks, "Task"))
+        .filter(|task| text(*task, "IsNull") != Some("1"))
+        .collect();
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    for task in tasks.iter() {
+        *names
+            .entry(text(*task, "Name").unwrap_or_default())
+            .or_default() += 1;
+    }
+
+    // the tasks that the next task can be in, with their outline levels
+    let mut open: Vec<(usize, FeatureID)> = vec![];
+    let mut features = vec![];
+    for task in tasks {
+        let offset = task.range().start;
+        let provenance = Provenance {
+            source: name.to_string(),
+            line: document.text_pos_at(offset).row as usize,
+            offset,
+        };
+        let mut feature = feature(task, &names, &teams, &mut interner).map_err(|error| {
+            ProgramIngesterError::Located {
+                location: provenance.to_string(),
+                error: Box::new(error),
+            }
+        })?;
+        let level = level(task).map_err(|error| ProgramIngesterError::Located {
+            location: provenance.to_string(),
+            error: Box::new(error),
+        })?;
+
+        while open
+            .last()
+            .is_some_and(|(open_level, _)| *open_level >= level)
+        {
+            open.pop();
+        }
+        feature.parent_id = open.last().map(|(_, parent_id)| parent_id.clone());
+        feature.program_id = program_id.clone();
+        feature.provenance = Some(provenance);
+        open.push((level, feature.id.clone()));
+
+        let predecessors = elements(task, "PredecessorLink").count();
+        if predecessors > 0 {
+            tracing::debug!(
+                feature_id = feature.id.as_str(),
+                predecessors,
+                "predecessor links are not kept"
+            );
+        }
+        features.push(feature);
+    }
+
+    // without a summary task, the top level tasks would each be the root of a program
+    let top_level: Vec<usize> = (0..features.len())
+        .filter(|index| features[*index].parent_id.is_none())
+        .collect();
+    if top_level.len() > 1 {
+        // the summary task's UID is 0, so the root is named the way a task sharing the project's name would be
+        let ids: HashSet<&str> = features.iter().map(|feature| feature.id.as_str()).collect();
+        let root_id = std::iter::once(program_id.to_string())
+            .chain((0..).map(|uid| format!("{program_id}#{uid}")))
+            .find(|id| !ids.contains(id.as_str()))
+            .expect("there are fewer tasks than UIDs");
+        let root_id = interner.intern(&root_id);
+        let offset = project.range().start;
+        let root = summary(
+            root_id,
+            program_id.clone(),
+            top_level.iter().map(|index| &features[*index]),
+            Provenance {
+                source: name.to_string(),
+                line: document.text_pos_at(offset).row as usize,
+                offset,
+            },
+            &mut interner,
+        );
+        for index in top_level {
+            features[index].parent_id = Some(root.id.clone());
+        }
+        features.insert(0, root);
+    }
+    Ok(features)
+}
+
+// a root feature for the project, spanning its top level tasks
+fn summary<'a>(
+    id: FeatureID,
+    program_id: FeatureID,
+    top_level: impl Iterator<Item = &'a RawFeature> + Clone,
+    provenance: Provenance,
+    interner: &mut Interner,
+) -> RawFeature {
+    let status = |status: &str| {
+        top_level
+            .clone()
+            .all(|feature| feature.progress_status == status)
+    };
+    let progress_status = if status(COMPLETE_STATUS) {
+        COMPLETE_STATUS
+    } else if status(NOT_STARTED_STATUS) {
+        NOT_STARTED_STATUS
+    } else {
+        IN_PROGRESS_STATUS
+    };
+    RawFeature {
+        id,
+        parent_id: None,
+        program_id,
+        progress_status: interner.intern(progress_status),
+        assigned_team: interner.intern(UNASSIGNED_TEAM),
+        start_date: top_level
+            .clone()
+            .map(|feature| feature.start_date)
+            .min()
+            .expect("there are several top level tasks"),
+        end_date: top_level
+            .map(|feature| feature.end_date)
+            .max()
+            .expect("there are several top level tasks"),
+        kind: FeatureKind::Task,
+        percent_complete: None,
+        planned_cost: None,
+        actual_cost: None,
+        estimate: None,
+        provenance: Some(provenance),
+    }
+}
+
+// the feature of a task, without its parent and program
+fn feature(
+    task: Node,
+    names: &HashMap<&str, usize>,
+    teams: &HashMap<&str, Vec<&str>>,
+    interner: &mut Interner,
+) -> Result<RawFeature, ProgramIngesterError> {
+    let uid = text(task, "UID").unwrap_or_default();
+    let name = text(task, "Name").unwrap_or_default();
+    let id = match (name, names.get(name)) {
+        ("", _) => format!("Task#{uid}"),
+        (name, Some(count)) if *count > 1 => format!("{name}#{uid}"),
+        (name, _) => name.to_string(),
+    };
+
+    let date = |element: &str| {
+        text(task, element)
+            .ok_or_else(|| {
+                ProgramIngesterError::InvalidProgramInput(format!(
+                    "The task '{id}' needs a {element}"
+                ))
+            })
+            .and_then(parse_date_time)
+    };
+    let start_date = date("Start")?;
+    let end_date = date("Finish")?;
+    let percent = match text(task, "PercentComplete") {
+        Some(percent) => percent.parse::<u8>().map_err(|_| {
+            ProgramIngesterError::InvalidProgramInput(format!(
+                "The PercentComplete '{percent}' of the task '{id}' needs to be a number from 0 to 100"
+            ))
+        })?,
+        None => 0,
+    }
+    .min(100);
+    let team = teams
+        .get(uid)
+        .map(|names| names.join(", "))
+        .unwrap_or_else(|| UNASSIGNED_TEAM.to_string());
+
+    Ok(RawFeature {
+        id: interner.intern(&id),
+        parent_id: None,
+        program_id: interner.intern(""),
+        progress_status: interner.intern(match percent {
+            100 => COMPLETE_STATUS,
+            0 => NOT_STARTED_STATUS,
+            _ => IN_PROGRESS_STATUS,
+        }),
+        assigned_team: interner.intern(&team),
+        start_date,
+        end_date,
+        kind: match text(task, "Milestone") {
+            Some("1") => FeatureKind::Milestone,
+            _ if start_date == end_date => FeatureKind::Milestone,
+            _ => FeatureKind::Task,
+        },
+        percent_complete: Some(percent),
+        planned_cost: None,
+        actual_cost: None,
+        estimate: None,
+        provenance: None,
+    })
+}
+
+// the depth of the task in the outline, the project's summary task is 0
+fn level(task: Node) -> Result<usize, ProgramIngesterError> {
+    match (text(task, "OutlineLevel"), text(task, "OutlineNumber")) {
+        (Some(level), _) => level.parse().map_err(|_| {
+            ProgramIngesterError::InvalidProgramInput(format!(
+                "The OutlineLevel '{level}' needs to be a number"
+            ))
+        }),
+        (None, Some("0")) => Ok(0),
+        (None, Some(number)) => Ok(number.split('.').count()),
+        (None, None) => Ok(1),
+    }
+}
+
+// the child elements with the name, in any namespace
+fn elements<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+// the text of the first child element with the name, when it is not empty
+fn text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+        .and_then(|element| element.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+// eg: 2023-01-02T08:00:00, without an offset
+fn parse_date_time(value: &str) -> Result<DateTime<FixedOffset>, ProgramIngesterError> {
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")?;
+    Ok(Utc.from_utc_datetime(&naive).into())
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use crate::{input::FeatureKind, output::ProgramGraph, source::Formats};
+
+    #[test]
+    fn test_project_import() {
+        let project = indoc! {r#"
+            <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+            <Project xmlns="http://schemas.microsoft.com/project">
+                <Name>Suite</Name>
+                <Tasks>
+                    <Task>
+                        <UID>0</UID>
+                        <Name>Suite</Name>
+                        <OutlineNumber>0</OutlineNumber>
+                        <OutlineLevel>0</OutlineLevel>
+                        <Start>2023-01-02T08:00:00</Start>
+                        <Finish>2023-03-31T17:00:00</Finish>
+                        <PercentComplete>40</PercentComplete>
+                    </Task>
+                    <Task>
+                        <UID>1</UID>
+                        <Name>Email</Name>
+                        <OutlineNumber>1</OutlineNumber>
+                        <Start>2023-01-02T08:00:00</Start>
+                        <Finish>2023-02-28T17:00:00</Finish>
+                        <PercentComplete>100</PercentComplete>
+                    </Task>
+                    <Task>
+                        <UID>2</UID>
+                        <Name>Review</Name>
+                        <OutlineNumber>1.1</OutlineNumber>
+                        <Start>2023-02-01T08:00:00</Start>
+                        <Finish>2023-02-03T17:00:00</Finish>
+                        <PercentComplete>100</PercentComplete>
+                    </Task>
+                    <Task>
+                        <UID>3</UID>
+                        <Name>Chat</Name>
+                        <OutlineLevel>1</OutlineLevel>
+                        <Start>2023-03-01T08:00:00</Start>
+                        <Finish>2023-03-31T17:00:00</Finish>
+                        <PercentComplete>0</PercentComplete>
+                        <PredecessorLink>
+                            <PredecessorUID>1</PredecessorUID>
+                            <Type>1</Type>
+                        </PredecessorLink>
+                    </Task>
+                    <Task>
+                        <UID>4</UID>
+                        <Name>Review</Name>
+                        <OutlineLevel>2</OutlineLevel>
+                        <Start>2023-03-20T08:00:00</Start>
+                        <Finish>2023-03-21T17:00:00</Finish>
+                    </Task>
+                    <Task>
+                        <UID>5</UID>
+                        <IsNull>1</IsNull>
+                    </Task>
+                    <Task>
+                        <UID>6</UID>
+                        <Name>Launch</Name>
+                        <OutlineLevel>1</OutlineLevel>
+                        <Start>2023-03-31T17:00:00</Start>
+                        <Finish>2023-03-31T17:00:00</Finish>
+                        <Milestone>1</Milestone>
+                    </Task>
+                </Tasks>
+                <Resources>
+                    <Resource><UID>1</UID><Name>TeamB</Name></Resource>
+                    <Resource><UID>2</UID><Name>TeamC</Name></Resource>
+                </Resources>
+                <Assignments>
+                    <Assignment><TaskUID>1</TaskUID><ResourceUID>1</ResourceUID></Assignment>
+                    <Assignment><TaskUID>3</TaskUID><ResourceUID>1</ResourceUID></Assignment>
+                    <Assignment><TaskUID>3</TaskUID><ResourceUID>2</ResourceUID></Assignment>
+                    <Assignment><TaskUID>6</TaskUID><ResourceUID>-65535</ResourceUID></Assignment>
+                </Assignments>
+            </Project>
+        "#};
+        let formats = Formats::new();
+        let format = formats
+            .by_path("plan.xml".as_ref())
+            .expect("xml should be registered");
+        let features = format
+            .ingest("plan.xml", Box::new(project.as_bytes()))
+            .expect("test input should be checked")
+            .features;
+
+        let summary: Vec<(&str, Option<&str>, &str, &str)> = features
+            .iter()
+            .map(|feature| {
+                (
+                    feature.id.as_str(),
+                    feature.parent_id.as_deref(),
+                    feature.assigned_team.as_str(),
+                    feature.progress_status.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Suite", None, "Unassigned", "InProgress"),
+                ("Email", Some("Suite"), "TeamB", "Complete"),
+                ("Review#2", Some("Email"), "Unassigned", "Complete"),
+                ("Chat", Some("Suite"), "TeamB, TeamC", "NotStarted"),
+                ("Review#4", Some("Chat"), "Unassigned", "NotStarted"),
+                ("Launch", Some("Suite"), "Unassigned", "NotStarted"),
+            ]
+        );
+        assert!(features.iter().all(|feature| feature.program_id == "Suite"));
+        assert_eq!(features[0].percent_complete, Some(40));
+        assert_eq!(
+            features[0].start_date.to_rfc3339(),
+            "2023-01-02T08:00:00+00:00"
+        );
+        assert_eq!(features[5].kind, FeatureKind::Milestone);
+        assert_eq!(
+            features[1].provenance.as_ref().unwrap().to_string(),
+            "plan.xml:14"
+        );
+
+        let graph = ProgramGraph::from(features);
+        assert_eq!(graph.programs.len(), 1);
+        assert_eq!(graph.programs[0].root.subfeatures.len(), 3);
+
+        let error = format
+            .ingest(
+                "plan.xml",
+                Box::new(project.replace("2023-02-28T17:00:00", "soon").as_bytes()),
+            )
+            .err()
+            .expect("the finish is not valid");
+        assert!(error.to_string().starts_with("plan.xml:14: "), "{error}");
+
+        // without the summary task, the top level tasks are under a root named after the project
+        let start = project.find("<Task>").expect("there are tasks");
+        let end = project[start..]
+            .find("</Task>")
+            .expect("the task is closed")
+            + start
+            + 7;
+        let features = format
+            .ingest(
+                "plan.xml",
+                Box::new(project.replace(&project[start..end], "").as_bytes()),
+            )
+            .expect("test input should be checked")
+            .features;
+        assert_eq!(features[0].id, "Suite");
+        assert_eq!(features[0].progress_status, "InProgress");
+        assert_eq!(
+            features[0].end_date.to_rfc3339(),
+            "2023-03-31T17:00:00+00:00"
+        );
+        assert_eq!(
+            features[0].provenance.as_ref().unwrap().to_string(),
+            "plan.xml:2"
+        );
+        assert_eq!(
+            features
+                .iter()
+                .filter(|feature| feature.parent_id.as_deref() == Some("Suite"))
+                .count(),
+            3
+        );
+        let graph = ProgramGraph::from(features);
+        assert_eq!(graph.programs.len(), 1);
+        assert_eq!(graph.programs[0].root.subfeatures.len(), 3);
+
+        // a top level task with the project's name keeps it, and the root gets the summary task's UID
+        let features = format
+            .ingest(
+                "plan.xml",
+                Box::new(
+                    project
+                        .replace(&project[start..end], "")
+                        .replace("<Name>Chat</Name>", "<Name>Suite</Name>")
+                        .as_bytes(),
+                ),
+            )
+            .expect("test input should be checked")
+            .features;
+        let ids: Vec<(&str, Option<&str>)> = features
+            .iter()
+            .map(|feature| (feature.id.as_str(), feature.parent_id.as_deref()))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("Suite#0", None),
+                ("Email", Some("Suite#0")),
+                ("Review#2", Some("Email")),
+                ("Suite", Some("Suite#0")),
+                ("Review#4", Some("Suite")),
+                ("Launch", Some("Suite#0")),
+            ]
+        );
+        let graph = ProgramGraph::from(features);
+        assert_eq!(graph.programs.len(), 1);
+        assert_eq!(graph.programs[0].root.flatten().len(), 6);
+    }
+}
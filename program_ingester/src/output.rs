@@ -1,9 +1,12 @@
 /// This needs documentation then I can write the main story
-use chrono::FixedOffset;
-use serde::{Serialize, Serializer};
-
-use crate::input::{FeatureDataAndChildren, FeatureID, FeatureMap, RawFeature};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize, Serializer};
 
+use crate::input::{
+    FeatureDataAndChildren, FeatureID, FeatureKind, FeatureMap, Provenance, RawFeature,
+    ThreePointEstimate, COMPLETE_STATUS, IN_PROGRESS_STATUS,
+};
+use crate::{symbol::Symbol, wbs::WbsCode};
 
 /// The lines #[derive(Debug, Serialize, Clone)] use Rust's "derive" macro to automatically generate implementations for the "Debug",
 /// "Serialize", and "Clone" traits for the Feature struct. This means that instances of Feature can be debugged, serialized
@@ -13,18 +16,154 @@ use crate::input::{FeatureDataAndChildren, FeatureID, FeatureMap, RawFeature};
 /// The line #[serde(serialize_with = "odered_features")] uses Serde's "serde" attribute to specify that the "subfeatures"
 /// field should be serialized using a custom serialization function named "odered_features". This allows for custom logic
 /// to be used when serializing the subfeatures field.
-#[derive(Debug, Serialize, Clone)]
+///
+/// Features are serialised without their provenance, unless the graph is serialised [ProgramGraph::with_provenance].
+#[derive(Debug, Deserialize, Clone)]
 pub struct Feature {
     #[serde(rename = "feature")]
-    pub id: String,
-    pub progress_status: String,
-    pub assigned_team: String,
+    pub id: FeatureID,
+    /// The outline number of the feature, eg: `1.2.3`, see [crate::wbs]
+    #[serde(default)]
+    pub wbs: Option<WbsCode>,
+    pub progress_status: Symbol,
+    pub assigned_team: Symbol,
     pub start_date: chrono::DateTime<FixedOffset>,
     pub end_date: chrono::DateTime<FixedOffset>,
-    #[serde(serialize_with = "odered_features")]
+    #[serde(default)]
+    pub kind: FeatureKind,
+    #[serde(default)]
+    pub percent_complete: Option<u8>,
+    #[serde(default)]
+    pub planned_cost: Option<f64>,
+    #[serde(default)]
+    pub actual_cost: Option<f64>,
+    #[serde(default)]
+    pub estimate: Option<ThreePointEstimate>,
+    /// Where the feature was read from, only serialised through [ProgramGraph::with_provenance]
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
     pub subfeatures: Vec<Feature>,
 }
 
+impl Serialize for Feature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        FeatureView::new(self, false).serialize(serializer)
+    }
+}
+
+/// The serialised form of a [Feature] and its subfeatures, with or without their provenance
+#[derive(Serialize, Clone)]
+struct FeatureView<'a> {
+    #[serde(rename = "feature")]
+    id: &'a FeatureID,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wbs: Option<&'a WbsCode>,
+    progress_status: &'a Symbol,
+    assigned_team: &'a Symbol,
+    start_date: DateTime<FixedOffset>,
+    end_date: DateTime<FixedOffset>,
+    kind: FeatureKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percent_complete: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    planned_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimate: Option<ThreePointEstimate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provenance: Option<&'a Provenance>,
+    #[serde(serialize_with = "odered_features")]
+    subfeatures: Vec<FeatureView<'a>>,
+}
+
+impl<'a> FeatureView<'a> {
+    fn new(feature: &'a Feature, with_provenance: bool) -> Self {
+        FeatureView {
+            id: &feature.id,
+            wbs: feature.wbs.as_ref(),
+            progress_status: &feature.progress_status,
+            assigned_team: &feature.assigned_team,
+            start_date: feature.start_date,
+            end_date: feature.end_date,
+            kind: feature.kind,
+            percent_complete: feature.percent_complete,
+            planned_cost: feature.planned_cost,
+            actual_cost: feature.actual_cost,
+            estimate: feature.estimate,
+            provenance: feature.provenance.as_ref().filter(|_| with_provenance),
+            subfeatures: feature
+                .subfeatures
+                .iter()
+                .map(|subfeature| FeatureView::new(subfeature, with_provenance))
+                .collect(),
+        }
+    }
+}
+
+impl Feature {
+    /// Copy the data of an ingested feature, with its already resolved subfeatures
+    pub(crate) fn from_raw(feature_data: &RawFeature, subfeatures: Vec<Feature>) -> Self {
+        Feature {
+            id: feature_data.id.clone(),
+            wbs: None,
+            start_date: feature_data.start_date,
+            end_date: feature_data.end_date,
+            assigned_team: feature_data.assigned_team.clone(),
+            progress_status: feature_data.progress_status.clone(),
+            kind: feature_data.kind,
+            percent_complete: feature_data.percent_complete,
+            planned_cost: feature_data.planned_cost,
+            actual_cost: feature_data.actual_cost,
+            estimate: feature_data.estimate,
+            provenance: feature_data.provenance.clone(),
+            subfeatures,
+        }
+    }
+
+    pub fn is_milestone(&self) -> bool {
+        self.kind == FeatureKind::Milestone
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// Whether any work has been done, or the progress_status says it is [IN_PROGRESS_STATUS]
+    pub fn is_started(&self) -> bool {
+        self.progress() > 0.0 || self.progress_status == IN_PROGRESS_STATUS
+    }
+
+    /// The fraction of the work that is done, from 0.0 to 1.0
+    ///
+    /// Without an explicit percent_complete, the feature is either done (progress_status is [COMPLETE_STATUS]) or not started.
+    pub fn progress(&self) -> f64 {
+        match self.percent_complete {
+            Some(percent) => f64::from(percent) / 100.0,
+            None if self.progress_status == COMPLETE_STATUS => 1.0,
+            None => 0.0,
+        }
+    }
+
+    /// Collect this feature and all of its descendants, depth first
+    pub fn flatten(&self) -> Vec<&Feature> {
+        let mut features = vec![self];
+        for subfeature in self.subfeatures.iter() {
+            features.extend(subfeature.flatten());
+        }
+        features
+    }
+
+    fn strip_provenance(&mut self) {
+        self.provenance = None;
+        for subfeature in self.subfeatures.iter_mut() {
+            subfeature.strip_provenance();
+        }
+    }
+}
 
 /// The given code defines a function named "odered_features", which is a custom serializer for instances of the Feature struct.
 /// The function takes in a slice of Feature objects, "value", and a Serde serializer, "serializer".
@@ -38,9 +177,9 @@ pub struct Feature {
 /// In this case, the method is used to convert the input slice of Feature objects, "value", into an owned vector of Feature objects.
 /// This is necessary because the sorting operation needs to modify the contents of the vector, and a reference to the original slice cannot be modified.
 /// By creating an owned copy, the original data remains unchanged, and the sort operation can be performed on the copy.
-
-//// Custom serializer for [Feature]
-fn odered_features<S>(value: &[Feature], serializer: S) -> Result<S::Ok, S::Error>
+///
+/// Custom serializer for [Feature]
+fn odered_features<S>(value: &[FeatureView], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
@@ -50,7 +189,21 @@ where
     value.serialize(serializer)
 }
 
+/// A [ProgramGraph] that is serialised with the provenance of its features, see [ProgramGraph::with_provenance]
+#[derive(Serialize)]
+pub struct WithProvenance<'a> {
+    programs: Vec<ProgramView<'a>>,
+}
+
+/// The serialised form of a [Program], see [WithProvenance]
+#[derive(Serialize)]
+struct ProgramView<'a> {
+    id: &'a Symbol,
+    root: FeatureView<'a>,
+}
 /// Implement PartialEq so that we can compare [Feature]s in an ordered way.
+///
+/// The provenance and WBS code are not compared, so the same plan read from different files is equal.
 impl PartialEq for Feature {
     fn eq(&self, other: &Self) -> bool {
         // sort the subfeatures by start_date date before equality check
@@ -64,22 +217,93 @@ impl PartialEq for Feature {
             && self.assigned_team == other.assigned_team
             && self.start_date == other.start_date
             && self.end_date == other.end_date
+            && self.kind == other.kind
+            && self.percent_complete == other.percent_complete
+            && self.planned_cost == other.planned_cost
+            && self.actual_cost == other.actual_cost
+            && self.estimate == other.estimate
             && these_subfeatures == those_subfeatures
     }
 }
 
-#[derive(Debug, Serialize, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Program {
-    pub id: String,
+    pub id: Symbol,
     pub root: Feature,
 }
 
-#[derive(Debug, Default, Serialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct ProgramGraph {
     #[serde(serialize_with = "odered_programs")]
     pub programs: Vec<Program>,
 }
 
+impl ProgramGraph {
+    /// Serialise the graph with where each feature was read from, which is left out by default
+    pub fn with_provenance(&self) -> WithProvenance<'_> {
+        let mut programs: Vec<ProgramView> = self
+            .programs
+            .iter()
+            .map(|program| ProgramView {
+                id: &program.id,
+                root: FeatureView::new(&program.root, true),
+            })
+            .collect();
+        // in the order of [ProgramGraph]'s own serialisation
+        programs.sort_by_key(|program| program.root.start_date);
+        WithProvenance { programs }
+    }
+
+    /// Forget where the features were read from
+    pub fn without_provenance(mut self) -> Self {
+        for program in self.programs.iter_mut() {
+            program.root.strip_provenance();
+        }
+        self
+    }
+
+    /// The features of the graph as ingested features, the reverse of [ProgramGraph::from].
+    ///
+    /// Programs and subfeatures are in start_date order, each feature before its subfeatures.
+    pub fn to_raw_features(&self) -> Vec<RawFeature> {
+        fn collect(
+            program_id: &Symbol,
+            parent_id: Option<&FeatureID>,
+            feature: &Feature,
+            features: &mut Vec<RawFeature>,
+        ) {
+            features.push(RawFeature {
+                id: feature.id.clone(),
+                parent_id: parent_id.cloned(),
+                program_id: program_id.clone(),
+                progress_status: feature.progress_status.clone(),
+                assigned_team: feature.assigned_team.clone(),
+                start_date: feature.start_date,
+                end_date: feature.end_date,
+                kind: feature.kind,
+                percent_complete: feature.percent_complete,
+                planned_cost: feature.planned_cost,
+                actual_cost: feature.actual_cost,
+                estimate: feature.estimate,
+                provenance: feature.provenance.clone(),
+            });
+            let mut subfeatures: Vec<&Feature> = feature.subfeatures.iter().collect();
+            subfeatures.sort_by_key(|subfeature| subfeature.start_date);
+            for subfeature in subfeatures {
+                collect(program_id, Some(&feature.id), subfeature, features);
+            }
+        }
+
+        let mut programs: Vec<&Program> = self.programs.iter().collect();
+        programs.sort_by_key(|program| program.root.start_date);
+        let mut features = vec![];
+        for program in programs {
+            collect(&program.id, None, &program.root, &mut features);
+        }
+        features
+    }
+}
+
 // /// Custom serializer for features
 /// Custom serializer for [Program]
 fn odered_programs<S>(value: &[Program], serializer: S) -> Result<S::Ok, S::Error>
@@ -105,24 +329,22 @@ impl PartialEq for ProgramGraph {
     }
 }
 
-
 /// This code takes a vector of RawFeature objects and transforms it into a ProgramGraph object.
 /// It does so by first creating a mapping of parent to children feature IDs
 /// and then using this mapping to resolve the subfeatures of each feature.
-
+///
 /// The first step in the transformation is to build the mapping by upserting
 /// each feature and its parent. If a feature or its parent already exists in the mapping,
 /// it is updated, otherwise it is inserted with the given information.
-
+///
 /// Once the mapping is built, the code filters out the root features,
 /// which are the features that don't have a parent. For each root feature,
 /// the code creates a Program object by resolving its subfeatures and then pushes it into the ProgramGraph object.
-
+///
 /// The code uses a helper function, resolve_subfeatures,
 /// to resolve the subfeatures of a feature. It takes a list of child feature
 /// IDs and the mapping and returns a list of Feature objects by filtering the mapping and transforming the filtered values into Feature objects.
-
-
+///
 /// Transform a vector of [`RawFeature`] into a [`ProgramGraph`]
 impl From<Vec<RawFeature>> for ProgramGraph {
     fn from(value: Vec<RawFeature>) -> Self {
@@ -138,20 +360,18 @@ impl From<Vec<RawFeature>> for ProgramGraph {
                     if let Some(feature_data) = value.feature_data {
                         child_ids.contains(&feature_data.id) // todo: use feature_id, remove if/else
                     } else {
-                        tracing::debug!(feature_id, "no feature_data found");
+                        tracing::debug!(feature_id = feature_id.as_str(), "no feature_data found");
                         false
                     }
                 })
                 //.filter_map(|value| {
                 .filter_map(|(feature_id, value)| {
-                    tracing::debug!(feature_id, "...");
-                    value.feature_data.map(|feature_data| Feature {
-                        id: feature_data.id.clone(),
-                        start_date: feature_data.start_date,
-                        end_date: feature_data.end_date,
-                        assigned_team: feature_data.assigned_team.clone(),
-                        progress_status: feature_data.progress_status.clone(),
-                        subfeatures: resolve_subfeatures(value.children.clone(), mappings),
+                    tracing::debug!(feature_id = feature_id.as_str(), "...");
+                    value.feature_data.map(|feature_data| {
+                        Feature::from_raw(
+                            feature_data,
+                            resolve_subfeatures(value.children.clone(), mappings),
+                        )
                     })
                 })
                 .collect()
@@ -217,14 +437,10 @@ impl From<Vec<RawFeature>> for ProgramGraph {
             if let Some(feature_data) = root.feature_data {
                 let program = Program {
                     id: feature_data.program_id.clone(),
-                    root: Feature {
-                        id: feature_data.id.clone(),
-                        start_date: feature_data.start_date,
-                        end_date: feature_data.end_date,
-                        assigned_team: feature_data.assigned_team.clone(),
-                        progress_status: feature_data.progress_status.clone(),
-                        subfeatures: resolve_subfeatures(root.children.clone(), &mappings),
-                    },
+                    root: Feature::from_raw(
+                        feature_data,
+                        resolve_subfeatures(root.children.clone(), &mappings),
+                    ),
                 };
                 graph.programs.push(program);
             } else {
@@ -232,13 +448,14 @@ impl From<Vec<RawFeature>> for ProgramGraph {
             }
         }
 
+        graph.number_wbs();
         graph
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::input::RawFeature;
+    use crate::input::{FeatureKind, RawFeature};
 
     use super::{Feature, Program, ProgramGraph};
     use chrono::DateTime;
@@ -256,6 +473,12 @@ mod test {
                     .expect("test dates should be checked"),
                 end_date: DateTime::parse_from_rfc3339("2023-11-30T00:00:00.000Z")
                     .expect("test dates should be checked"),
+                kind: FeatureKind::Task,
+                percent_complete: None,
+                planned_cost: None,
+                actual_cost: None,
+                estimate: None,
+                provenance: None,
             },
             RawFeature {
                 id: "b".into(),
@@ -267,6 +490,12 @@ mod test {
                     .expect("test dates should be checked"),
                 end_date: DateTime::parse_from_rfc3339("2023-11-20T00:00:00.000Z")
                     .expect("test dates should be checked"),
+                kind: FeatureKind::Task,
+                percent_complete: None,
+                planned_cost: None,
+                actual_cost: None,
+                estimate: None,
+                provenance: None,
             },
         ];
 
@@ -275,20 +504,34 @@ mod test {
                 id: "t1".into(),
                 root: Feature {
                     id: "a".into(),
+                    wbs: None,
                     progress_status: "s1".into(),
                     assigned_team: "s1".into(),
                     start_date: DateTime::parse_from_rfc3339("2023-10-01T00:00:00.000Z")
                         .expect("test dates should be checked"),
                     end_date: DateTime::parse_from_rfc3339("2023-11-30T00:00:00.000Z")
                         .expect("test dates should be checked"),
+                    kind: FeatureKind::Task,
+                    percent_complete: None,
+                    planned_cost: None,
+                    actual_cost: None,
+                    estimate: None,
+                    provenance: None,
                     subfeatures: vec![Feature {
                         id: "b".into(),
+                        wbs: None,
                         progress_status: "s2".into(),
                         assigned_team: "s1".into(),
                         start_date: DateTime::parse_from_rfc3339("2023-10-20T00:00:00.000Z")
                             .expect("test dates should be checked"),
                         end_date: DateTime::parse_from_rfc3339("2023-11-20T00:00:00.000Z")
                             .expect("test dates should be checked"),
+                        kind: FeatureKind::Task,
+                        percent_complete: None,
+                        planned_cost: None,
+                        actual_cost: None,
+                        estimate: None,
+                        provenance: None,
                         subfeatures: vec![],
                     }],
                 },
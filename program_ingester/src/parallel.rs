@@ -0,0 +1,176 @@
+//! Parse large inputs in parallel, when the `parallel` feature is enabled.
+//!
+//! The input is split into chunks at line boundaries, and the chunks are parsed on the rayon thread pool.
+//! The result is the same as [Ingester::from_source] for the same input: the features and events in order,
+//! with the same provenance, or the error of the first line that is not valid.
+use rayon::prelude::*;
+
+use crate::{
+    errors::ProgramIngesterError,
+    input::{Ingester, Line, Provenance},
+    symbol::Interner,
+};
+
+// chunks smaller than this are not worth handing to another thread
+const MIN_CHUNK_SIZE: usize = 64 * 1024;
+
+// the text of a chunk, with where it starts in the input
+struct Chunk<'a> {
+    text: &'a str,
+    offset: usize,
+    first_line: usize,
+}
+
+impl Ingester {
+    /// Parse a whole input (eg: a file that was read, or memory-mapped and checked to be UTF-8) in parallel
+    pub fn from_str_parallel(name: &str, input: &str) -> Result<Self, ProgramIngesterError> {
+        let chunk_size = MIN_CHUNK_SIZE.max(input.len() / (rayon::current_num_threads() * 4));
+        Ingester::from_str_in_chunks(name, input, chunk_size)
+    }
+
+    /// Like [Ingester::from_str_parallel], in chunks of about `chunk_size` bytes (ending at line boundaries)
+    pub fn from_str_in_chunks(
+        name: &str,
+        input: &str,
+        chunk_size: usize,
+    ) -> Result<Self, ProgramIngesterError> {
+        let chunks = chunks(input, chunk_size);
+
+        let parsed: Vec<Result<Vec<Line>, ProgramIngesterError>> = chunks
+            .par_iter()
+            .map(|chunk| {
+                let mut interner = Interner::new();
+                let mut offset = chunk.offset;
+                chunk
+                    .text
+                    .split_inclusive('\n')
+                    .enumerate()
+                    .map(|(index, text)| {
+                        let provenance = Provenance {
+                            source: name.into(),
+                            line: chunk.first_line + index,
+                            offset,
+                        };
+                        offset += text.len();
+                        // remove the trailing \n
+                        Line::parse_with(text.trim_end(), provenance, &mut interner)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut ingester = Ingester {
+            features: vec![],
+            events: vec![],
+        };
+        // the chunks had their own interners, share the names across all of them
+        let mut interner = Interner::new();
+        // the first error of the first chunk that failed is the first error of the input
+        for lines in parsed {
+            for mut line in lines? {
+                line.intern(&mut interner);
+                match line {
+                    Line::Feature(feature) => ingester.features.push(feature),
+                    Line::Event(event) => ingester.events.push(event),
+                }
+            }
+        }
+        Ok(ingester)
+    }
+}
+
+// split the input into chunks of about `size` bytes, ending at line boundaries
+fn chunks(input: &str, size: usize) -> Vec<Chunk<'_>> {
+    let mut texts = vec![];
+    let mut rest = input;
+    while !rest.is_empty() {
+        // `size` can be inside a character, but the byte after a '\n' always starts one
+        let end = match rest
+            .as_bytes()
+            .get(size..)
+            .and_then(|tail| tail.iter().position(|&byte| byte == b'\n'))
+        {
+            Some(newline) => size + newline + 1,
+            None => rest.len(),
+        };
+        texts.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    // the line numbers need the number of lines in every chunk before
+    let line_counts: Vec<usize> = texts
+        .par_iter()
+        .map(|text| text.bytes().filter(|&byte| byte == b'\n').count())
+        .collect();
+
+    let mut offset = 0;
+    let mut first_line = 1;
+    texts
+        .into_iter()
+        .zip(line_counts)
+        .map(|(text, lines)| {
+            let chunk = Chunk {
+                text,
+                offset,
+                first_line,
+            };
+            offset += text.len();
+            first_line += lines;
+            chunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use super::chunks;
+    use crate::input::Ingester;
+
+    fn input(lines: usize) -> String {
+        (0..lines)
+            .map(|line| match line % 10 {
+                // multi-byte characters, so that chunks are not always cut at a character boundary
+                0 => format!("2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program{line} InProgress Équipe null->root{line}\n"),
+                5 => format!("@2023-03-01T00:00:00Z update feature{} status=Complete\n", line - 1),
+                _ => format!("2023-02-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program{} Complete TeamB root{}->feature{line}\n", line / 10 * 10, line / 10 * 10),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_as_sequential() {
+        let input = input(1_000);
+        let sequential = Ingester::from_source("plan.txt", BufReader::new(input.as_bytes()))
+            .expect("test input should be checked");
+
+        // chunks of every size are cut at a line, so chunks of 1 KB hold about 10 lines
+        for size in [1, 999, 1_000, 1_001] {
+            let chunks = chunks(&input, size);
+            assert!(chunks.len() > 50);
+            assert!(chunks.iter().all(|chunk| chunk.text.ends_with('\n')));
+        }
+        // a size inside a character still ends the chunk at the next line
+        let texts: Vec<&str> = chunks("é\né\n", 1).iter().map(|chunk| chunk.text).collect();
+        assert_eq!(texts, ["é\n", "é\n"]);
+
+        let parallel = Ingester::from_str_in_chunks("plan.txt", &input, 1_000)
+            .expect("test input should be checked");
+        assert_eq!(parallel.features, sequential.features);
+        assert_eq!(parallel.events, sequential.events);
+        let parallel =
+            Ingester::from_str_parallel("plan.txt", &input).expect("test input should be checked");
+        assert_eq!(parallel.features, sequential.features);
+
+        // the first error wins, wherever the chunks are split
+        let broken = format!("{input}not a feature\n{input}also not a feature\n");
+        let sequential = Ingester::from_source("plan.txt", BufReader::new(broken.as_bytes()))
+            .err()
+            .expect("line 1001 is not valid");
+        let parallel = Ingester::from_str_in_chunks("plan.txt", &broken, 1_000)
+            .err()
+            .expect("line 1001 is not valid");
+        assert_eq!(parallel.to_string(), sequential.to_string());
+    }
+}
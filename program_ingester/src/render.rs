@@ -0,0 +1,891 @@
+//! Draw a [ProgramGraph] as a Gantt chart, or export it for other tools.
+//!
+//! Features are drawn as bars, and milestones as diamonds, on a shared time scale from the earliest start_date
+//! to the latest end_date of the graph. Programs and subfeatures are ordered by start_date.
+//!
+//! Every output is a [Renderer], which draws the [Chart] picked by the [RenderOptions] (time range, scale, grouping and filters).
+//! The built-in renderers are `terminal`, `svg`, `mermaid`, `html`, `csv` and `ics` (see [crate::ics]), and [Renderers] is the registry
+//! to pick one by name or by file extension. Other crates can implement [Renderer] and [Renderers::register] their own.
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    ics::Ics,
+    input::{FeatureID, FeatureKind},
+    output::{Feature, Program, ProgramGraph},
+    symbol::Symbol,
+};
+
+const BAR: char = '█';
+const DIAMOND: char = '◆';
+const EMPTY: char = ' ';
+
+// the size of a column of the time scale, and of a row, in drawings
+const PIXELS_PER_COLUMN: usize = 8;
+const ROW_HEIGHT: usize = 24;
+const LABEL_PIXELS_PER_CHAR: usize = 8;
+
+/// What to draw, and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// The size of the time scale, in columns of text (drawings use 8 pixels per column)
+    pub width: usize,
+
+    /// Only draw the features that end after this time, and start the time scale there
+    pub from: Option<DateTime<FixedOffset>>,
+
+    /// Only draw the features that start before this time, and end the time scale there
+    pub until: Option<DateTime<FixedOffset>>,
+
+    pub group_by: Grouping,
+
+    /// Only draw the features assigned to this team
+    pub team: Option<String>,
+
+    /// Only draw the features with this progress status
+    pub status: Option<String>,
+
+    /// Only draw milestones
+    pub milestones: bool,
+
+    /// Only draw the features without subfeatures, eg: the deliverables and milestones
+    pub leaves: bool,
+
+    /// Put the WBS code (see [crate::wbs]) in front of each feature ID
+    pub wbs: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            width: 80,
+            from: None,
+            until: None,
+            group_by: Grouping::default(),
+            team: None,
+            status: None,
+            milestones: false,
+            leaves: false,
+            wbs: false,
+        }
+    }
+}
+
+/// How the rows of a chart are grouped
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// A group per program, with the feature hierarchy
+    #[default]
+    Program,
+    /// A group per assigned team, without the hierarchy
+    Team,
+}
+
+/// A way of drawing (or exporting) a [ProgramGraph]
+pub trait Renderer {
+    /// The name to pick the renderer with, eg: `svg`
+    fn name(&self) -> &str;
+
+    /// The file extension (without the `.`) of the output
+    fn extension(&self) -> &str;
+
+    fn render(
+        &self,
+        graph: &ProgramGraph,
+        options: &RenderOptions,
+        writer: &mut dyn Write,
+    ) -> io::Result<()>;
+}
+
+/// The registry of [Renderer]s, with the built-in ones by default
+pub struct Renderers {
+    renderers: Vec<Box<dyn Renderer>>,
+}
+
+impl Default for Renderers {
+    fn default() -> Self {
+        let mut renderers = Renderers { renderers: vec![] };
+        renderers.register(Terminal);
+        renderers.register(Svg);
+        renderers.register(Mermaid);
+        renderers.register(Html);
+        renderers.register(Csv);
+        renderers.register(Ics::default());
+        renderers
+    }
+}
+
+impl Renderers {
+    pub fn new() -> Self {
+        Renderers::default()
+    }
+
+    /// Add a renderer. It replaces a renderer with the same name, and takes over its extension from earlier renderers.
+    pub fn register(&mut self, renderer: impl Renderer + 'static) {
+        self.renderers
+            .retain(|existing| existing.name() != renderer.name());
+        self.renderers.push(Box::new(renderer));
+    }
+
+    /// The names of the renderers, in the order they were registered
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.renderers.iter().map(|renderer| renderer.name())
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&dyn Renderer> {
+        self.renderers
+            .iter()
+            .find(|renderer| renderer.name() == name)
+            .map(AsRef::as_ref)
+    }
+
+    /// The renderer of the extension (case insensitive), the latest registered one if several renderers have it
+    pub fn by_extension(&self, extension: &str) -> Option<&dyn Renderer> {
+        self.renderers
+            .iter()
+            .rev()
+            .find(|renderer| renderer.extension().eq_ignore_ascii_case(extension))
+            .map(AsRef::as_ref)
+    }
+
+    /// The renderer of the file, from its extension
+    pub fn by_path(&self, path: &Path) -> Option<&dyn Renderer> {
+        path.extension()
+            .and_then(|extension| self.by_extension(&extension.to_string_lossy()))
+    }
+}
+
+/// Draw the graph as a text chart, with `width` columns for the time scale
+pub fn terminal<W: Write>(graph: &ProgramGraph, width: usize, writer: &mut W) -> io::Result<()> {
+    let options = RenderOptions {
+        width,
+        ..RenderOptions::default()
+    };
+    Terminal.render(graph, &options, writer)
+}
+
+/// The features to draw, grouped and ordered, on the time scale picked by the [RenderOptions]
+#[derive(Debug)]
+pub struct Chart<'a> {
+    pub groups: Vec<Group<'a>>,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+}
+
+/// A program or a team, see [Grouping]
+#[derive(Debug)]
+pub struct Group<'a> {
+    pub name: &'a Symbol,
+    pub rows: Vec<Row<'a>>,
+}
+
+#[derive(Debug)]
+pub struct Row<'a> {
+    /// How deep the feature is in the hierarchy (0 for roots, and when grouped by team)
+    pub depth: usize,
+    pub program_id: &'a Symbol,
+    pub parent_id: Option<&'a FeatureID>,
+    /// The IDs of the features above this one, from the program's root down to the parent
+    pub ancestors: Vec<&'a FeatureID>,
+    pub feature: &'a Feature,
+}
+
+impl<'a> Chart<'a> {
+    /// The rows of the graph that match the options, `None` when there is nothing to draw
+    pub fn new(graph: &'a ProgramGraph, options: &RenderOptions) -> Option<Self> {
+        let mut programs: Vec<&Program> = graph.programs.iter().collect();
+        programs.sort_by(|a, b| (a.root.start_date, &a.id).cmp(&(b.root.start_date, &b.id)));
+
+        let mut groups: Vec<Group> = programs
+            .into_iter()
+            .map(|program| {
+                let mut rows = vec![];
+                collect_rows(&program.id, &[], &program.root, &mut rows);
+                rows.retain(|row| shown(row.feature, options));
+                Group {
+                    name: &program.id,
+                    rows,
+                }
+            })
+            .collect();
+
+        if options.group_by == Grouping::Team {
+            let mut teams: BTreeMap<&Symbol, Vec<Row>> = BTreeMap::new();
+            for row in groups.into_iter().flat_map(|group| group.rows) {
+                teams
+                    .entry(&row.feature.assigned_team)
+                    .or_default()
+                    .push(Row { depth: 0, ..row });
+            }
+            groups = teams
+                .into_iter()
+                .map(|(name, mut rows)| {
+                    rows.sort_by(|a, b| {
+                        (a.feature.start_date, &a.feature.id)
+                            .cmp(&(b.feature.start_date, &b.feature.id))
+                    });
+                    Group { name, rows }
+                })
+                .collect();
+        }
+        groups.retain(|group| !group.rows.is_empty());
+
+        let rows = groups.iter().flat_map(|group| group.rows.iter());
+        let start = options
+            .from
+            .or_else(|| rows.clone().map(|row| row.feature.start_date).min())?;
+        let end = options
+            .until
+            .or_else(|| rows.clone().map(|row| row.feature.end_date).max())?;
+        Some(Chart { groups, start, end })
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &Row<'a>> {
+        self.groups.iter().flat_map(|group| group.rows.iter())
+    }
+
+    // the width of the widest label, indented
+    fn label_width(&self, wbs: bool) -> usize {
+        self.rows()
+            .map(|row| row.depth * 2 + row.label(wbs).chars().count())
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl Row<'_> {
+    /// The feature ID, after its WBS code when `wbs` is set
+    pub fn label(&self, wbs: bool) -> String {
+        match (&self.feature.wbs, wbs) {
+            (Some(code), true) => format!("{code} {}", self.feature.id),
+            _ => self.feature.id.to_string(),
+        }
+    }
+}
+
+// depth first, with subfeatures ordered by start_date
+fn collect_rows<'a>(
+    program_id: &'a Symbol,
+    ancestors: &[&'a FeatureID],
+    feature: &'a Feature,
+    rows: &mut Vec<Row<'a>>,
+) {
+    rows.push(Row {
+        depth: ancestors.len(),
+        program_id,
+        parent_id: ancestors.last().copied(),
+        ancestors: ancestors.to_vec(),
+        feature,
+    });
+
+    let ancestors = [ancestors, &[&feature.id]].concat();
+    let mut subfeatures: Vec<&Feature> = feature.subfeatures.iter().collect();
+    subfeatures.sort_by(|a, b| (a.start_date, &a.id).cmp(&(b.start_date, &b.id)));
+    for subfeature in subfeatures {
+        collect_rows(program_id, &ancestors, subfeature, rows);
+    }
+}
+
+// whether the feature passes the filters and overlaps the time range
+fn shown(feature: &Feature, options: &RenderOptions) -> bool {
+    options
+        .team
+        .as_ref()
+        .is_none_or(|team| feature.assigned_team == *team)
+        && options
+            .status
+            .as_ref()
+            .is_none_or(|status| feature.progress_status == *status)
+        && (!options.milestones || feature.is_milestone())
+        && (!options.leaves || feature.subfeatures.is_empty())
+        && options.from.is_none_or(|from| feature.end_date >= from)
+        && options
+            .until
+            .is_none_or(|until| feature.start_date <= until)
+}
+
+// maps dates onto columns
+struct Scale {
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    width: usize,
+}
+
+impl Scale {
+    fn column(&self, date: DateTime<FixedOffset>) -> usize {
+        let span = (self.end - self.start).num_seconds().max(1);
+        let offset = (date - self.start).num_seconds().clamp(0, span);
+        (offset as i128 * (self.width - 1) as i128 / span as i128) as usize
+    }
+
+    fn bar(&self, feature: &Feature) -> String {
+        let from = self.column(feature.start_date);
+        let to = self.column(feature.end_date).max(from);
+
+        (0..self.width)
+            .map(|column| match column {
+                column if feature.is_milestone() && column == from => DIAMOND,
+                column if !feature.is_milestone() && (from..=to).contains(&column) => BAR,
+                _ => EMPTY,
+            })
+            .collect()
+    }
+}
+
+/// A text chart, for the terminal
+pub struct Terminal;
+
+impl Renderer for Terminal {
+    fn name(&self) -> &str {
+        "terminal"
+    }
+
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn render(
+        &self,
+        graph: &ProgramGraph,
+        options: &RenderOptions,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let Some(chart) = Chart::new(graph, options) else {
+            return Ok(());
+        };
+        let label_width = chart.label_width(options.wbs);
+        let team_width = chart
+            .rows()
+            .map(|row| row.feature.assigned_team.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let scale = Scale {
+            start: chart.start,
+            end: chart.end,
+            width: options.width.max(1),
+        };
+
+        writeln!(
+            writer,
+            "{:label_width$} {:team_width$}  {} .. {}",
+            "",
+            "",
+            chart.start.format("%Y-%m-%d"),
+            chart.end.format("%Y-%m-%d")
+        )?;
+
+        for group in chart.groups.iter() {
+            writeln!(writer, "{}", group.name)?;
+            for row in group.rows.iter() {
+                let label = format!("{}{}", "  ".repeat(row.depth), row.label(options.wbs));
+                writeln!(
+                    writer,
+                    "{label:label_width$} {:team_width$} |{}|",
+                    row.feature.assigned_team,
+                    scale.bar(row.feature)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A standalone SVG drawing
+pub struct Svg;
+
+impl Renderer for Svg {
+    fn name(&self) -> &str {
+        "svg"
+    }
+
+    fn extension(&self) -> &str {
+        "svg"
+    }
+
+    fn render(
+        &self,
+        graph: &ProgramGraph,
+        options: &RenderOptions,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let chart = Chart::new(graph, options);
+        let label_width = chart
+            .as_ref()
+            .map(|chart| chart.label_width(options.wbs))
+            .unwrap_or_default()
+            * LABEL_PIXELS_PER_CHAR
+            + 16;
+        let scale_width = options.width.max(1) * PIXELS_PER_COLUMN;
+        let rows = chart
+            .as_ref()
+            .map(|chart| chart.groups.len() + chart.rows().count())
+            .unwrap_or_default();
+
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="12">"#,
+            label_width + scale_width,
+            (rows + 1) * ROW_HEIGHT
+        )?;
+        if let Some(chart) = chart {
+            let scale = Scale {
+                start: chart.start,
+                end: chart.end,
+                width: scale_width,
+            };
+            writeln!(
+                writer,
+                r#"  <text x="{label_width}" y="16">{} .. {}</text>"#,
+                chart.start.format("%Y-%m-%d"),
+                chart.end.format("%Y-%m-%d")
+            )?;
+
+            let mut y = ROW_HEIGHT;
+            for group in chart.groups.iter() {
+                writeln!(
+                    writer,
+                    r#"  <text x="4" y="{}" font-weight="bold">{}</text>"#,
+                    y + 16,
+                    escape(group.name)
+                )?;
+                y += ROW_HEIGHT;
+
+                for row in group.rows.iter() {
+                    let feature = row.feature;
+                    writeln!(
+                        writer,
+                        r#"  <text x="{}" y="{}">{}</text>"#,
+                        4 + row.depth * 2 * LABEL_PIXELS_PER_CHAR,
+                        y + 16,
+                        escape(&row.label(options.wbs))
+                    )?;
+
+                    let from = label_width + scale.column(feature.start_date);
+                    let title = format!(
+                        "<title>{}: {}, {}, {} - {}</title>",
+                        escape(&feature.id),
+                        escape(&feature.assigned_team),
+                        escape(&feature.progress_status),
+                        feature.start_date.format("%Y-%m-%d"),
+                        feature.end_date.format("%Y-%m-%d")
+                    );
+                    if feature.is_milestone() {
+                        let middle = y + ROW_HEIGHT / 2;
+                        writeln!(
+                            writer,
+                            r#"  <polygon points="{from},{} {},{middle} {from},{} {},{middle}" fill="{}">{title}</polygon>"#,
+                            middle - 8,
+                            from + 8,
+                            middle + 8,
+                            from.saturating_sub(8),
+                            colour(feature)
+                        )?;
+                    } else {
+                        let to = (label_width + scale.column(feature.end_date)).max(from);
+                        writeln!(
+                            writer,
+                            r#"  <rect x="{from}" y="{}" width="{}" height="{}" fill="{}">{title}</rect>"#,
+                            y + 4,
+                            (to - from).max(1),
+                            ROW_HEIGHT - 8,
+                            colour(feature)
+                        )?;
+                    }
+                    y += ROW_HEIGHT;
+                }
+            }
+        }
+        writeln!(writer, "</svg>")
+    }
+}
+
+// done features are green, started ones blue, and the others grey
+fn colour(feature: &Feature) -> &'static str {
+    if feature.is_complete() {
+        "#4caf50"
+    } else if feature.is_started() {
+        "#2196f3"
+    } else {
+        "#9e9e9e"
+    }
+}
+
+// make text safe to put in XML and HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// An HTML page with the SVG drawing
+pub struct Html;
+
+impl Renderer for Html {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn render(
+        &self,
+        graph: &ProgramGraph,
+        options: &RenderOptions,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, r#"<html lang="en">"#)?;
+        writeln!(
+            writer,
+            r#"<head><meta charset="utf-8"><title>Programs</title></head>"#
+        )?;
+        writeln!(writer, "<body>")?;
+        Svg.render(graph, options, writer)?;
+        writeln!(writer, "</body>")?;
+        writeln!(writer, "</html>")
+    }
+}
+
+/// A Mermaid gantt diagram, eg: for Markdown documents
+pub struct Mermaid;
+
+impl Renderer for Mermaid {
+    fn name(&self) -> &str {
+        "mermaid"
+    }
+
+    fn extension(&self) -> &str {
+        "mmd"
+    }
+
+    fn render(
+        &self,
+        graph: &ProgramGraph,
+        options: &RenderOptions,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(writer, "gantt")?;
+        writeln!(writer, "    dateFormat YYYY-MM-DD")?;
+        let Some(chart) = Chart::new(graph, options) else {
+            return Ok(());
+        };
+
+        for group in chart.groups.iter() {
+            writeln!(writer, "    section {}", mermaid_text(group.name))?;
+            for row in group.rows.iter() {
+                let feature = row.feature;
+                let mut tags = vec![];
+                if feature.is_complete() {
+                    tags.push("done");
+                } else if feature.is_started() {
+                    tags.push("active");
+                }
+                if feature.is_milestone() {
+                    tags.push("milestone");
+                }
+                tags.push("");
+
+                let id = mermaid_id(&feature.id);
+                let start = feature.start_date.format("%Y-%m-%d");
+                match feature.is_milestone() {
+                    true => writeln!(
+                        writer,
+                        "    {} :{}{id}, {start}, 0d",
+                        mermaid_text(&row.label(options.wbs)),
+                        tags.join(", ")
+                    )?,
+                    false => writeln!(
+                        writer,
+                        "    {} :{}{id}, {start}, {}",
+                        mermaid_text(&row.label(options.wbs)),
+                        tags.join(", "),
+                        feature.end_date.format("%Y-%m-%d")
+                    )?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Mermaid separates the parts of a task with `:`, and `#` and `;` start comments and entities
+fn mermaid_text(text: &str) -> String {
+    text.replace([':', '#', ';'], " ")
+}
+
+// task IDs can only have letters, digits, `_` and `-`
+fn mermaid_id(text: &str) -> String {
+    text.chars()
+        .map(|char| match char {
+            char if char.is_ascii_alphanumeric() || char == '-' => char,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// A flat table of the features, a row per feature in start_date order, eg: for spreadsheets.
+///
+/// Besides the columns of the CSV input format (see [crate::source::CsvSource]), which it can be read back with,
+/// it has the WBS code and depth of each feature, its path from the program's root, its duration in days and its number of subfeatures.
+pub struct Csv;
+
+/// The columns written by [Csv]
+pub const CSV_COLUMNS: [&str; 17] = [
+    "program",
+    "wbs",
+    "feature",
+    "parent",
+    "depth",
+    "path",
+    "team",
+    "status",
+    "start",
+    "end",
+    "duration_days",
+    "children",
+    "kind",
+    "percent",
+    "cost",
+    "actual",
+    "estimate",
+];
+
+impl Renderer for Csv {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn render(
+        &self,
+        graph: &ProgramGraph,
+        options: &RenderOptions,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        fn optional<T: ToString>(value: Option<T>) -> String {
+            value.map(|value| value.to_string()).unwrap_or_default()
+        }
+
+        let mut csv = csv::Writer::from_writer(writer);
+        csv.write_record(CSV_COLUMNS)?;
+        if let Some(chart) = Chart::new(graph, options) {
+            for row in chart.rows() {
+                let feature = row.feature;
+                let path = row
+                    .ancestors
+                    .iter()
+                    .chain([&&feature.id])
+                    .map(|id| id.as_str())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let duration =
+                    (feature.end_date - feature.start_date).num_seconds() as f64 / 86_400.0;
+                csv.write_record([
+                    row.program_id.as_str(),
+                    &optional(feature.wbs.as_ref()),
+                    &feature.id,
+                    row.parent_id.map(Symbol::as_str).unwrap_or_default(),
+                    &row.ancestors.len().to_string(),
+                    &path,
+                    &feature.assigned_team,
+                    &feature.progress_status,
+                    &feature.start_date.to_rfc3339(),
+                    &feature.end_date.to_rfc3339(),
+                    &duration.to_string(),
+                    &feature.subfeatures.len().to_string(),
+                    match feature.kind {
+                        FeatureKind::Task => "task",
+                        FeatureKind::Milestone => "milestone",
+                    },
+                    &optional(feature.percent_complete),
+                    &optional(feature.planned_cost),
+                    &optional(feature.actual_cost),
+                    &optional(feature.estimate.map(|estimate| {
+                        format!(
+                            "{}/{}/{}",
+                            estimate.optimistic, estimate.most_likely, estimate.pessimistic
+                        )
+                    })),
+                ])?;
+            }
+        }
+        csv.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use super::{terminal, Csv, Grouping, RenderOptions, Renderer, Renderers};
+    use crate::{
+        output::ProgramGraph,
+        source::{CsvFormat, SourceFormat},
+        testing::graph,
+    };
+
+    const PLAN: &str = indoc! {"
+        2023-01-01T00:00:00.000Z 2023-01-11T00:00:00.000Z program1 InProgress TeamA null->Suite
+        2023-01-01T00:00:00.000Z 2023-01-06T00:00:00.000Z program1 Complete TeamB Suite->Email
+        2023-01-11T00:00:00.000Z 2023-01-11T00:00:00.000Z program1 InProgress TeamB Suite->Release
+    "};
+
+    // the output of the renderer registered with the name
+    fn render(name: &str, graph: &ProgramGraph, options: &RenderOptions) -> String {
+        let mut output = vec![];
+        Renderers::new()
+            .by_name(name)
+            .expect("the renderer should be registered")
+            .render(graph, options, &mut output)
+            .expect("writing to a Vec should work");
+        String::from_utf8(output).expect("output is UTF-8")
+    }
+
+    #[test]
+    fn test_terminal_chart() {
+        let mut chart = vec![];
+        terminal(&graph(PLAN), 11, &mut chart).expect("writing to a Vec should work");
+
+        let expected = indoc! {"
+                             2023-01-01 .. 2023-01-11
+            program1
+            Suite     TeamA |███████████|
+              Email   TeamB |██████     |
+              Release TeamB |          ◆|
+        "};
+        assert_eq!(String::from_utf8(chart).expect("chart is UTF-8"), expected);
+    }
+
+    #[test]
+    fn test_grouping_by_team() {
+        let by_team = RenderOptions {
+            width: 11,
+            group_by: Grouping::Team,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render("terminal", &graph(PLAN), &by_team),
+            indoc! {"
+                               2023-01-01 .. 2023-01-11
+                TeamA
+                Suite   TeamA |███████████|
+                TeamB
+                Email   TeamB |██████     |
+                Release TeamB |          ◆|
+            "}
+        );
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let with_wbs = RenderOptions {
+            wbs: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render("mermaid", &graph(PLAN), &with_wbs),
+            indoc! {"
+                gantt
+                    dateFormat YYYY-MM-DD
+                    section program1
+                    1 Suite :active, Suite, 2023-01-01, 2023-01-11
+                    1.1 Email :done, Email, 2023-01-01, 2023-01-06
+                    1.2 Release :active, milestone, Release, 2023-01-11, 0d
+            "}
+        );
+    }
+
+    #[test]
+    fn test_only_milestones() {
+        let milestones = RenderOptions {
+            milestones: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render("csv", &graph(PLAN), &milestones),
+            indoc! {"
+                program,wbs,feature,parent,depth,path,team,status,start,end,duration_days,children,kind,percent,cost,actual,estimate
+                program1,1.2,Release,Suite,1,Suite/Release,TeamB,InProgress,2023-01-11T00:00:00+00:00,2023-01-11T00:00:00+00:00,0,0,milestone,,,,
+            "}
+        );
+    }
+
+    #[test]
+    fn test_svg_and_html() {
+        let graph = graph(PLAN);
+        let svg = render("svg", &graph, &RenderOptions::default());
+        // bars for the tasks, a diamond for the milestone
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert!(render("html", &graph, &RenderOptions::default()).contains(&svg));
+    }
+
+    #[test]
+    fn test_registry() {
+        let renderers = Renderers::new();
+        assert_eq!(
+            renderers.by_path("plan.mmd".as_ref()).unwrap().name(),
+            "mermaid"
+        );
+        assert!(renderers.by_name("pdf").is_none());
+    }
+
+    #[test]
+    fn test_end_before_start() {
+        // parsed, but only reported by validate
+        let graph = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2023-01-11T00:00:00.000Z program1 InProgress TeamA null->Suite
+            2023-01-08T00:00:00.000Z 2023-01-02T00:00:00.000Z program1 InProgress TeamB Suite->Backwards
+        "});
+
+        for name in Renderers::new().names() {
+            let output = render(name, &graph, &RenderOptions::default());
+            assert!(output.contains("Backwards"), "{name}: {output}");
+        }
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let graph = graph(indoc! {"
+            2023-01-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamA null->Suite cost=100 actual=20
+            2023-02-01T00:00:00.000Z 2023-02-15T12:00:00.000Z program1 InProgress TeamB Suite->Chat percent=40 estimate=5/8/15
+            2023-01-01T00:00:00.000Z 2023-01-31T00:00:00.000Z program1 Complete TeamB Suite->Email
+            2023-01-10T00:00:00.000Z 2023-01-20T00:00:00.000Z program1 Complete TeamC Email->EmailSearch
+            2023-03-31T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamA Suite->Release
+        "});
+
+        let mut csv = vec![];
+        Csv.render(&graph, &RenderOptions::default(), &mut csv)
+            .expect("writing to a Vec should work");
+        let csv = String::from_utf8(csv).expect("output is UTF-8");
+
+        // depth first, in start_date order
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 6);
+        assert!(rows[1].starts_with("program1,1,Suite,,0,Suite,TeamA,InProgress,"));
+        assert!(rows[2].starts_with("program1,1.1,Email,Suite,1,Suite/Email,"));
+        assert!(rows[2].contains(",30,1,task,"));
+        assert!(rows[3].starts_with("program1,1.1.1,EmailSearch,Email,2,Suite/Email/EmailSearch,"));
+        assert!(rows[4].ends_with(",14.5,0,task,40,,,5/8/15"));
+        assert!(rows[5].contains(",0,0,milestone,"));
+
+        let read_back = CsvFormat
+            .ingest("plan.csv", Box::new(csv.as_bytes()))
+            .expect("the export should be read back");
+        assert_eq!(ProgramGraph::from(read_back.features), graph);
+    }
+}
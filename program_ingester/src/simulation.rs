@@ -0,0 +1,289 @@
+//! Monte Carlo schedule risk simulation over the features of a [ProgramGraph].
+//!
+//! In every iteration, each feature with a [ThreePointEstimate] gets a duration drawn from the triangular distribution
+//! of its estimate, starting at its start_date. Features without an estimate keep their planned end_date.
+//! A feature cannot finish before its subfeatures, so its finish is the latest of its own and its subfeatures' finishes,
+//! and the program finishes when its root feature does.
+//!
+//! The random numbers come from a generator seeded by [Simulation::seed], so a simulation is reproducible.
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::Serialize;
+
+use crate::{
+    errors::ProgramIngesterError,
+    input::{FeatureID, ThreePointEstimate},
+    output::{Feature, Program, ProgramGraph},
+    symbol::Symbol,
+};
+
+/// The settings of a simulation run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Simulation {
+    pub iterations: usize,
+    pub seed: u64,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation {
+            iterations: 10_000,
+            seed: 0,
+        }
+    }
+}
+
+/// The forecast finish of a program
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ProgramForecast {
+    pub program_id: Symbol,
+    pub planned_finish: DateTime<FixedOffset>,
+    pub p50: DateTime<FixedOffset>,
+    pub p80: DateTime<FixedOffset>,
+    pub p95: DateTime<FixedOffset>,
+
+    /// The share of iterations (from 0.0 to 1.0) in which each feature's own work finished last, driving the program's finish.
+    ///
+    /// Only a feature's own finish counts, not its subfeatures', so summary features are not always critical.
+    pub criticality: BTreeMap<FeatureID, f64>,
+}
+
+/// The forecasts of every program in a [ProgramGraph], ordered by program ID
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub iterations: usize,
+    pub seed: u64,
+    pub programs: Vec<ProgramForecast>,
+}
+
+impl Simulation {
+    pub fn new(iterations: usize, seed: u64) -> Self {
+        Simulation { iterations, seed }
+    }
+
+    /// Simulate every program, failing when an estimate would finish beyond the dates that can be represented
+    pub fn run(&self, graph: &ProgramGraph) -> Result<SimulationReport, ProgramIngesterError> {
+        // every sample is at most the pessimistic estimate, so checking it checks them all
+        for feature in graph
+            .programs
+            .iter()
+            .flat_map(|program| program.root.flatten())
+        {
+            if let Some(estimate) = feature.estimate {
+                if finish_after(feature.start_date, estimate.pessimistic).is_none() {
+                    return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                        "The estimate of '{}' ends too far from its start date to be simulated",
+                        feature.id
+                    )));
+                }
+            }
+        }
+
+        let mut rng = SplitMix64(self.seed);
+
+        // iterate programs in a stable order, so the random numbers are drawn in the same order on every run
+        let mut programs: Vec<&Program> = graph.programs.iter().collect();
+        programs.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let programs = programs
+            .into_iter()
+            .map(|program| self.forecast(program, &mut rng))
+            .collect();
+
+        Ok(SimulationReport {
+            iterations: self.iterations,
+            seed: self.seed,
+            programs,
+        })
+    }
+
+    fn forecast(&self, program: &Program, rng: &mut SplitMix64) -> ProgramForecast {
+        let features = program.root.flatten();
+        let mut critical_counts: BTreeMap<&FeatureID, usize> =
+            features.iter().map(|feature| (&feature.id, 0)).collect();
+        let mut finishes = Vec::with_capacity(self.iterations);
+
+        for _ in 0..self.iterations {
+            let mut feature_finishes = vec![];
+            let finish = simulate_finish(&program.root, rng, &mut feature_finishes);
+
+            for (feature_id, feature_finish) in feature_finishes {
+                if feature_finish == finish {
+                    *critical_counts.entry(feature_id).or_default() += 1;
+                }
+            }
+            finishes.push(finish);
+        }
+        finishes.sort();
+
+        let planned_finish = features
+            .iter()
+            .map(|feature| feature.end_date)
+            .max()
+            .expect("a program always has a root feature");
+        let percentile = |p: f64| -> DateTime<FixedOffset> {
+            let index = ((p * finishes.len() as f64).ceil() as usize).saturating_sub(1);
+            finishes.get(index).copied().unwrap_or(planned_finish)
+        };
+
+        ProgramForecast {
+            program_id: program.id.clone(),
+            planned_finish,
+            p50: percentile(0.50),
+            p80: percentile(0.80),
+            p95: percentile(0.95),
+            criticality: critical_counts
+                .into_iter()
+                .map(|(feature_id, count)| {
+                    let share = if self.iterations == 0 {
+                        0.0
+                    } else {
+                        count as f64 / self.iterations as f64
+                    };
+                    (feature_id.clone(), share)
+                })
+                .collect(),
+        }
+    }
+}
+
+// simulate the finish of a feature and its subfeatures, recording the own finish of each of them
+fn simulate_finish<'a>(
+    feature: &'a Feature,
+    rng: &mut SplitMix64,
+    feature_finishes: &mut Vec<(&'a FeatureID, DateTime<FixedOffset>)>,
+) -> DateTime<FixedOffset> {
+    let own_finish = match feature.estimate {
+        Some(estimate) => {
+            let days = sample_triangular(&estimate, rng.next_f64());
+            // the estimates are checked before the iterations start
+            finish_after(feature.start_date, days).unwrap_or(feature.end_date)
+        }
+        None => feature.end_date,
+    };
+
+    // subfeatures are not stored in a stable order, so sort them before drawing their random numbers
+    let mut subfeatures: Vec<&Feature> = feature.subfeatures.iter().collect();
+    subfeatures.sort_by(|a, b| (a.start_date, &a.id).cmp(&(b.start_date, &b.id)));
+
+    let finish = subfeatures
+        .into_iter()
+        .map(|subfeature| simulate_finish(subfeature, rng, feature_finishes))
+        .fold(own_finish, |latest, finish| latest.max(finish));
+
+    feature_finishes.push((&feature.id, own_finish));
+    finish
+}
+
+// the time `days` after the start, `None` when it cannot be represented
+fn finish_after(start: DateTime<FixedOffset>, days: f64) -> Option<DateTime<FixedOffset>> {
+    let seconds = (days * 86_400.0).round();
+    // Duration::seconds panics beyond i64::MAX milliseconds
+    if !seconds.is_finite() || seconds.abs() >= (i64::MAX / 1_000) as f64 {
+        return None;
+    }
+    start.checked_add_signed(Duration::seconds(seconds as i64))
+}
+
+/// Draw a duration (in days) from the triangular distribution of the estimate, given a uniform sample from 0.0 to 1.0
+fn sample_triangular(estimate: &ThreePointEstimate, uniform: f64) -> f64 {
+    let ThreePointEstimate {
+        optimistic: a,
+        most_likely: c,
+        pessimistic: b,
+    } = *estimate;
+
+    if b <= a {
+        return a;
+    }
+
+    let mode = (c - a) / (b - a);
+    if uniform < mode {
+        a + (uniform * (b - a) * (c - a)).sqrt()
+    } else {
+        b - ((1.0 - uniform) * (b - a) * (b - c)).sqrt()
+    }
+}
+
+/// A small, seedable pseudo random number generator (SplitMix64), so that simulations are reproducible
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform sample from 0.0 (inclusive) to 1.0 (exclusive)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use super::Simulation;
+    use crate::{input::ThreePointEstimate, testing::graph};
+
+    const PLAN: &str = indoc! {"
+        2023-01-01T00:00:00.000Z 2023-01-02T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+        2023-01-01T00:00:00.000Z 2023-01-31T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email estimate=20/30/60
+        2023-01-01T00:00:00.000Z 2023-01-06T00:00:00.000Z program1 InProgress TeamC ProductivitySuite->Calendar estimate=3/5/8
+    "};
+
+    #[test]
+    fn test_seeded_simulation() {
+        let graph = graph(PLAN);
+        let report = Simulation::new(2_000, 42)
+            .run(&graph)
+            .expect("the estimates can be simulated");
+        assert_eq!(report, Simulation::new(2_000, 42).run(&graph).unwrap());
+
+        let forecast = &report.programs[0];
+        assert!(forecast.p50 <= forecast.p80 && forecast.p80 <= forecast.p95);
+    }
+
+    #[test]
+    fn test_criticality() {
+        let report = Simulation::new(2_000, 42)
+            .run(&graph(PLAN))
+            .expect("the estimates can be simulated");
+        let criticality = &report.programs[0].criticality;
+
+        // Email always finishes last, Calendar never does, and the root only finishes last through Email
+        assert_eq!(criticality["Email"], 1.0);
+        assert_eq!(criticality["ProductivitySuite"], 0.0);
+        assert_eq!(criticality["Calendar"], 0.0);
+
+        // a summary feature whose own work outlasts its subfeatures is critical itself
+        let plan = PLAN.replacen(
+            "null->ProductivitySuite",
+            "null->ProductivitySuite estimate=90/90/90",
+            1,
+        );
+        let report = Simulation::new(100, 42)
+            .run(&graph(&plan))
+            .expect("the estimates can be simulated");
+        let criticality = &report.programs[0].criticality;
+        assert_eq!(criticality["ProductivitySuite"], 1.0);
+        assert_eq!(criticality["Email"], 0.0);
+    }
+
+    #[test]
+    fn test_estimates_too_long_to_simulate() {
+        // estimates that the parser rejects can still come from JSON
+        let mut graph = graph(PLAN);
+        graph.programs[0].root.subfeatures[0].estimate = Some(ThreePointEstimate {
+            optimistic: 1.0,
+            most_likely: 2.0,
+            pessimistic: 1e9,
+        });
+        assert!(Simulation::new(10, 42).run(&graph).is_err());
+    }
+}
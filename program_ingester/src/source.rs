@@ -0,0 +1,590 @@
+//! Input formats. A [FeatureSource] is anything that produces features, one `Result` at a time,
+//! and a [SourceFormat] opens a reader (eg: a file) as a [FeatureSource].
+//!
+//! The built-in formats are:
+//! - `line`: one feature (or event) per line, see [RawFeature::from_str](std::str::FromStr) and [crate::events]
+//! - `csv`: a header row naming the columns (see [CsvSource]), then one feature per row
+//! - `json`: a [ProgramGraph], eg: the output of `convert`
+//! - `ics`: the events of an iCalendar file, see [IcsFormat]
+//! - `mspdi`: the tasks of a Microsoft Project XML export, see [MspdiFormat]
+//!
+//! [Formats] is the registry of formats, to pick one by name or by file extension.
+//! Other crates can implement [SourceFormat] and [Formats::register] their own.
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+use chrono::DateTime;
+use csv::StringRecord;
+
+use crate::{
+    errors::ProgramIngesterError,
+    ics::IcsFormat,
+    input::{
+        expand_paths, parse_cost, parse_percent, FeatureKind, Ingester, Line, Lines, Provenance,
+        RawFeature, ThreePointEstimate,
+    },
+    mspdi::MspdiFormat,
+    output::ProgramGraph,
+    symbol::Interner,
+};
+
+/// The features of an input, in order
+///
+/// Any iterator of features is a source, errors should be [ProgramIngesterError::Located] where possible.
+pub trait FeatureSource: Iterator<Item = Result<RawFeature, ProgramIngesterError>> {}
+
+impl<I: Iterator<Item = Result<RawFeature, ProgramIngesterError>>> FeatureSource for I {}
+
+/// A way of reading features
+pub trait SourceFormat {
+    /// The name to pick the format with, eg: `csv`
+    fn name(&self) -> &str;
+
+    /// The file extensions (without the `.`) of the format
+    fn extensions(&self) -> &[&str];
+
+    /// Read the features of a reader, `name` is the source of their [Provenance]
+    fn features<'a>(
+        &self,
+        name: &str,
+        reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn FeatureSource + 'a>;
+
+    /// Read a whole input, stopping at the first error
+    ///
+    /// Formats with events (like the line format) replace this to keep them.
+    fn ingest(
+        &self,
+        name: &str,
+        reader: Box<dyn BufRead + '_>,
+    ) -> Result<Ingester, ProgramIngesterError> {
+        Ok(Ingester {
+            features: self.features(name, reader).collect::<Result<_, _>>()?,
+            events: vec![],
+        })
+    }
+}
+
+/// The registry of [SourceFormat]s, with the built-in ones by default
+pub struct Formats {
+    formats: Vec<Box<dyn SourceFormat>>,
+}
+
+impl Default for Formats {
+    fn default() -> Self {
+        let mut formats = Formats { formats: vec![] };
+        formats.register(LineFormat);
+        formats.register(CsvFormat);
+        formats.register(JsonFormat);
+        formats.register(IcsFormat::default());
+        formats.register(MspdiFormat);
+        formats
+    }
+}
+
+impl Formats {
+    pub fn new() -> Self {
+        Formats::default()
+    }
+
+    /// Add a format. It replaces a format with the same name, and takes over its extensions from earlier formats.
+    pub fn register(&mut self, format: impl SourceFormat + 'static) {
+        self.formats
+            .retain(|existing| existing.name() != format.name());
+        self.formats.push(Box::new(format));
+    }
+
+    /// The names of the formats, in the order they were registered
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|format| format.name())
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&dyn SourceFormat> {
+        self.formats
+            .iter()
+            .find(|format| format.name() == name)
+            .map(AsRef::as_ref)
+    }
+
+    /// The format of the extension (case insensitive), the latest registered one if several formats have it
+    pub fn by_extension(&self, extension: &str) -> Option<&dyn SourceFormat> {
+        self.formats
+            .iter()
+            .rev()
+            .find(|format| {
+                format
+                    .extensions()
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(extension))
+            })
+            .map(AsRef::as_ref)
+    }
+
+    /// The format of the file, from its extension
+    pub fn by_path(&self, path: &Path) -> Option<&dyn SourceFormat> {
+        path.extension()
+            .and_then(|extension| self.by_extension(&extension.to_string_lossy()))
+    }
+
+    /// Read and merge several inputs, in order, like [Ingester::from_paths].
+    ///
+    /// Every file is read in `format`, or when it is `None`, in the format of its extension (the line format when it is not known).
+    pub fn ingest_paths<P: AsRef<Path>>(
+        &self,
+        format: Option<&dyn SourceFormat>,
+        inputs: &[P],
+    ) -> Result<Ingester, ProgramIngesterError> {
+        let mut merged = Ingester {
+            features: vec![],
+            events: vec![],
+        };
+        for path in expand_paths(inputs)? {
+            let name = path.display().to_string();
+            let format = format
+                .or_else(|| self.by_path(&path))
+                .unwrap_or(&LineFormat);
+            let file =
+                std::fs::File::open(&path).map_err(|error| ProgramIngesterError::Located {
+                    location: name.clone(),
+                    error: Box::new(error.into()),
+                })?;
+            let ingester = format.ingest(&name, Box::new(BufReader::new(file)))?;
+            merged.features.extend(ingester.features);
+            merged.events.extend(ingester.events);
+        }
+
+        // the files share their names, eg: the teams
+        let mut interner = Interner::new();
+        for feature in merged.features.iter_mut() {
+            feature.intern(&mut interner);
+        }
+        for event in merged.events.iter_mut() {
+            event.intern(&mut interner);
+        }
+        Ok(merged)
+    }
+}
+
+/// One feature per line, see [Ingester::from_source]
+pub struct LineFormat;
+
+impl SourceFormat for LineFormat {
+    fn name(&self) -> &str {
+        "line"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt", "log"]
+    }
+
+    /// The features of the lines, event lines are skipped
+    fn features<'a>(
+        &self,
+        name: &str,
+        reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn FeatureSource + 'a> {
+        Box::new(
+            Lines::new(name, reader, Interner::new()).filter_map(|line| match line {
+                Ok(Line::Feature(feature)) => Some(Ok(feature)),
+                Ok(Line::Event(_)) => None,
+                Err(error) => Some(Err(error)),
+            }),
+        )
+    }
+
+    fn ingest(
+        &self,
+        name: &str,
+        reader: Box<dyn BufRead + '_>,
+    ) -> Result<Ingester, ProgramIngesterError> {
+        Ingester::from_source(name, BufReader::new(reader))
+    }
+}
+
+/// A header row, then one feature per row
+pub struct CsvFormat;
+
+impl SourceFormat for CsvFormat {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv"]
+    }
+
+    fn features<'a>(
+        &self,
+        name: &str,
+        reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn FeatureSource + 'a> {
+        Box::new(CsvSource::new(name, reader))
+    }
+}
+
+/// A [ProgramGraph] as JSON, eg: the output of `convert`
+pub struct JsonFormat;
+
+impl SourceFormat for JsonFormat {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+
+    /// The features of the graph, programs and subfeatures ordered by start date
+    fn features<'a>(
+        &self,
+        name: &str,
+        reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn FeatureSource + 'a> {
+        match serde_json::from_reader::<_, ProgramGraph>(reader) {
+            Ok(graph) => {
+                let mut interner = Interner::new();
+                Box::new(graph.to_raw_features().into_iter().map(move |mut feature| {
+                    feature.intern(&mut interner);
+                    Ok(feature)
+                }))
+            }
+            Err(error) => Box::new(std::iter::once(Err(ProgramIngesterError::Located {
+                location: name.into(),
+                error: Box::new(error.into()),
+            }))),
+        }
+    }
+}
+
+/// The features of a CSV reader, with a header row naming the columns:
+/// - `feature`, `program`, `status`, `team`, `start` and `end` are needed
+/// - `parent` is the parent feature, a root when it is empty, `null`, or there is no such column
+/// - `kind` (task or milestone), `percent`, `cost`, `actual` and `estimate` are optional, like the attributes of the line format
+///
+/// Other columns are ignored. Like the line format, the source stops after the first error.
+pub struct CsvSource<R> {
+    name: String,
+    reader: csv::Reader<R>,
+    columns: Option<Columns>,
+    interner: Interner,
+    failed: bool,
+}
+
+impl<R: Read> CsvSource<R> {
+    pub fn new(name: impl Into<String>, reader: R) -> Self {
+        CsvSource {
+            name: name.into(),
+            reader: csv::Reader::from_reader(reader),
+            columns: None,
+            interner: Interner::new(),
+            failed: false,
+        }
+    }
+
+    // where the reader is, or where the error happened
+    fn located(
+        &self,
+        position: Option<&csv::Position>,
+        error: ProgramIngesterError,
+    ) -> ProgramIngesterError {
+        let position = position.unwrap_or_else(|| self.reader.position());
+        ProgramIngesterError::Located {
+            location: Provenance {
+                source: self.name.clone(),
+                line: position.line() as usize,
+                offset: position.byte() as usize,
+            }
+            .to_string(),
+            error: Box::new(error),
+        }
+    }
+
+    fn read_feature(&mut self) -> Option<Result<RawFeature, ProgramIngesterError>> {
+        if self.columns.is_none() {
+            let columns = match self.reader.headers() {
+                Ok(headers) => Columns::new(headers),
+                Err(error) => Err(error.into()),
+            };
+            match columns {
+                Ok(columns) => self.columns = Some(columns),
+                Err(error) => {
+                    // the header is the first line
+                    let mut header = csv::Position::new();
+                    header.set_line(1);
+                    return Some(Err(self.located(Some(&header), error)));
+                }
+            }
+        }
+        let columns = self.columns.as_ref()?;
+
+        let mut record = StringRecord::new();
+        match self.reader.read_record(&mut record) {
+            Ok(false) => None,
+            Ok(true) => {
+                let position = record
+                    .position()
+                    .cloned()
+                    .unwrap_or_else(csv::Position::new);
+                let provenance = Provenance {
+                    source: self.name.clone(),
+                    line: position.line() as usize,
+                    offset: position.byte() as usize,
+                };
+                Some(
+                    columns
+                        .feature(&record, &mut self.interner)
+                        .map(|mut feature| {
+                            feature.provenance = Some(provenance.clone());
+                            feature
+                        })
+                        .map_err(|error| ProgramIngesterError::Located {
+                            location: provenance.to_string(),
+                            error: Box::new(error),
+                        }),
+                )
+            }
+            Err(error) => {
+                let position = error.position().cloned();
+                Some(Err(self.located(position.as_ref(), error.into())))
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for CsvSource<R> {
+    type Item = Result<RawFeature, ProgramIngesterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let feature = self.read_feature();
+        self.failed = matches!(feature, Some(Err(_)));
+        feature
+    }
+}
+
+// the positions of the columns in the CSV header
+#[derive(Debug, Clone)]
+struct Columns {
+    feature: usize,
+    parent: Option<usize>,
+    program: usize,
+    status: usize,
+    team: usize,
+    start: usize,
+    end: usize,
+    kind: Option<usize>,
+    percent: Option<usize>,
+    cost: Option<usize>,
+    actual: Option<usize>,
+    estimate: Option<usize>,
+}
+
+impl Columns {
+    fn new(headers: &StringRecord) -> Result<Self, ProgramIngesterError> {
+        let find = |name: &str| headers.iter().position(|header| header.trim() == name);
+        let required = |name: &str| {
+            find(name).ok_or_else(|| {
+                ProgramIngesterError::InvalidProgramInput(format!(
+                    "The CSV header '{}' needs a '{name}' column",
+                    headers.iter().collect::<Vec<_>>().join(",")
+                ))
+            })
+        };
+
+        Ok(Columns {
+            feature: required("feature")?,
+            parent: find("parent"),
+            program: required("program")?,
+            status: required("status")?,
+            team: required("team")?,
+            start: required("start")?,
+            end: required("end")?,
+            kind: find("kind"),
+            percent: find("percent"),
+            cost: find("cost"),
+            actual: find("actual"),
+            estimate: find("estimate"),
+        })
+    }
+
+    fn feature(
+        &self,
+        record: &StringRecord,
+        interner: &mut Interner,
+    ) -> Result<RawFeature, ProgramIngesterError> {
+        let line = record.iter().collect::<Vec<_>>().join(",");
+        let field = |index: usize| record.get(index).unwrap_or_default().trim();
+        let optional = |index: Option<usize>| index.map(field).filter(|value| !value.is_empty());
+
+        let id = field(self.feature);
+        if id.is_empty() {
+            return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                "The row '{line}' needs a feature"
+            )));
+        }
+
+        let start_date = DateTime::parse_from_rfc3339(field(self.start))?;
+        let end_date = DateTime::parse_from_rfc3339(field(self.end))?;
+        let kind = match optional(self.kind) {
+            Some("task") => FeatureKind::Task,
+            Some("milestone") => FeatureKind::Milestone,
+            Some(kind) => {
+                return Err(ProgramIngesterError::InvalidProgramInput(format!(
+                    "The kind '{kind}' in '{line}' is not one of 'task', 'milestone'"
+                )))
+            }
+            // Zero length features are milestones, like in the line format
+            None if start_date == end_date => FeatureKind::Milestone,
+            None => FeatureKind::Task,
+        };
+
+        Ok(RawFeature {
+            id: interner.intern(id),
+            parent_id: optional(self.parent)
+                .filter(|parent_id| *parent_id != "null")
+                .map(|parent_id| interner.intern(parent_id)),
+            program_id: interner.intern(field(self.program)),
+            progress_status: interner.intern(field(self.status)),
+            assigned_team: interner.intern(field(self.team)),
+            start_date,
+            end_date,
+            kind,
+            percent_complete: optional(self.percent)
+                .map(|value| parse_percent(value, &line))
+                .transpose()?,
+            planned_cost: optional(self.cost)
+                .map(|value| parse_cost(value, &line))
+                .transpose()?,
+            actual_cost: optional(self.actual)
+                .map(|value| parse_cost(value, &line))
+                .transpose()?,
+            estimate: optional(self.estimate)
+                .map(|value| value.parse::<ThreePointEstimate>())
+                .transpose()?,
+            provenance: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use std::io::BufReader;
+
+    use super::{Formats, SourceFormat};
+    use crate::{
+        input::{Ingester, RawFeature},
+        output::ProgramGraph,
+    };
+
+    #[test]
+    fn test_formats_read_the_same_features() {
+        let line = indoc! {"
+            2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email percent=100 cost=10
+            2023-06-30T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Launch
+        "};
+        let csv = indoc! {"
+            program,feature,parent,team,status,start,end,percent,cost,notes
+            program1,ProductivitySuite,,TeamA,InProgress,2023-01-01T00:00:00.000Z,2023-12-31T00:00:00.000Z,,,
+            program1,Email,ProductivitySuite,TeamB,Complete,2023-01-01T00:00:00.000Z,2023-06-30T00:00:00.000Z,100,10,\"done, on time\"
+            program1,Launch,ProductivitySuite,TeamB,InProgress,2023-06-30T00:00:00.000Z,2023-06-30T00:00:00.000Z,,,
+        "};
+        let expected = Ingester::try_from(BufReader::new(line.as_bytes()))
+            .expect("test input should be checked")
+            .features;
+        let json = serde_json::to_string(&ProgramGraph::from(expected.clone()))
+            .expect("graph should serialise");
+
+        let formats = Formats::new();
+        let read = |format: &dyn SourceFormat, input: &str| {
+            format
+                .ingest("plan", Box::new(input.as_bytes()))
+                .expect("test input should be checked")
+                .features
+        };
+        let without_provenance = |features: Vec<RawFeature>| {
+            features
+                .into_iter()
+                .map(|feature| RawFeature {
+                    provenance: None,
+                    ..feature
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let from_csv = read(formats.by_name("csv").unwrap(), csv);
+        assert_eq!(
+            from_csv[1].provenance.as_ref().unwrap().to_string(),
+            "plan:3"
+        );
+        assert_eq!(
+            without_provenance(from_csv),
+            without_provenance(expected.clone())
+        );
+        assert_eq!(
+            without_provenance(read(formats.by_name("json").unwrap(), &json)),
+            without_provenance(expected)
+        );
+
+        assert_eq!(formats.by_extension("CSV").unwrap().name(), "csv");
+        assert_eq!(
+            formats.by_path("plans/a.txt".as_ref()).unwrap().name(),
+            "line"
+        );
+        assert!(formats.by_name("xml").is_none());
+
+        // the first error stops the source
+        let broken = csv.replace("2023-12-31", "yesterday");
+        let features: Vec<_> = formats
+            .by_name("csv")
+            .unwrap()
+            .features("plan", Box::new(broken.as_bytes()))
+            .collect();
+        assert_eq!(features.len(), 1);
+        let error = features[0].as_ref().expect_err("row 2 is not valid");
+        assert!(error.to_string().starts_with("plan:2: "), "{error}");
+
+        let missing = "program,feature\nprogram1,Email\n";
+        let error = formats
+            .by_name("csv")
+            .unwrap()
+            .ingest("plan", Box::new(missing.as_bytes()))
+            .err()
+            .expect("the header is missing columns");
+        assert!(
+            error.to_string().starts_with("plan:1: ")
+                && error.to_string().contains("needs a 'status' column"),
+            "{error}"
+        );
+
+        // rows that the CSV reader rejects are located too
+        let ragged = csv.replace(",,,\n", ",,\n");
+        let error = formats
+            .by_name("csv")
+            .unwrap()
+            .ingest("plan", Box::new(ragged.as_bytes()))
+            .err()
+            .expect("row 2 is missing a column");
+        assert!(error.to_string().starts_with("plan:2: "), "{error}");
+
+        // the line format and the ingester read lines the same way
+        let mut not_utf8 = line.as_bytes().to_vec();
+        not_utf8.extend(b"\xff\n");
+        let error = formats
+            .by_name("line")
+            .unwrap()
+            .features("plan", Box::new(&not_utf8[..]))
+            .find_map(Result::err)
+            .expect("line 4 is not UTF-8");
+        assert!(error.to_string().starts_with("plan:4: "), "{error}");
+        let from_source = Ingester::from_source("plan", BufReader::new(&not_utf8[..]))
+            .err()
+            .expect("line 4 is not UTF-8");
+        assert_eq!(from_source.to_string(), error.to_string());
+    }
+}
@@ -0,0 +1,142 @@
+//! Summary figures of a [ProgramGraph]
+use std::{collections::BTreeMap, fmt::Display};
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+
+use crate::{
+    output::{Feature, ProgramGraph},
+    symbol::Symbol,
+};
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+pub struct Stats {
+    pub programs: usize,
+    pub features: usize,
+    pub milestones: usize,
+
+    /// The deepest level of subfeatures, where a root feature is at depth 0
+    pub max_depth: usize,
+
+    /// The earliest start_date
+    pub start_date: Option<DateTime<FixedOffset>>,
+
+    /// The latest end_date
+    pub end_date: Option<DateTime<FixedOffset>>,
+
+    /// Number of features per assigned_team
+    pub teams: BTreeMap<Symbol, usize>,
+
+    /// Number of features per progress_status
+    pub statuses: BTreeMap<Symbol, usize>,
+}
+
+impl From<&ProgramGraph> for Stats {
+    fn from(graph: &ProgramGraph) -> Self {
+        fn depth(feature: &Feature) -> usize {
+            feature
+                .subfeatures
+                .iter()
+                .map(|subfeature| depth(subfeature) + 1)
+                .max()
+                .unwrap_or_default()
+        }
+
+        let mut stats = Stats {
+            programs: graph.programs.len(),
+            ..Default::default()
+        };
+
+        for program in graph.programs.iter() {
+            stats.max_depth = stats.max_depth.max(depth(&program.root));
+
+            for feature in program.root.flatten() {
+                stats.features += 1;
+                if feature.is_milestone() {
+                    stats.milestones += 1;
+                }
+                *stats
+                    .teams
+                    .entry(feature.assigned_team.clone())
+                    .or_default() += 1;
+                *stats
+                    .statuses
+                    .entry(feature.progress_status.clone())
+                    .or_default() += 1;
+                stats.start_date = Some(match stats.start_date {
+                    Some(start_date) => start_date.min(feature.start_date),
+                    None => feature.start_date,
+                });
+                stats.end_date = Some(match stats.end_date {
+                    Some(end_date) => end_date.max(feature.end_date),
+                    None => feature.end_date,
+                });
+            }
+        }
+
+        stats
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "programs:   {}", self.programs)?;
+        writeln!(f, "features:   {}", self.features)?;
+        writeln!(f, "milestones: {}", self.milestones)?;
+        writeln!(f, "max depth:  {}", self.max_depth)?;
+        if let (Some(start_date), Some(end_date)) = (self.start_date, self.end_date) {
+            writeln!(
+                f,
+                "dates:      {} .. {}",
+                start_date.to_rfc3339(),
+                end_date.to_rfc3339()
+            )?;
+        }
+        writeln!(f, "teams:")?;
+        for (team, count) in self.teams.iter() {
+            writeln!(f, "  {team}: {count}")?;
+        }
+        writeln!(f, "statuses:")?;
+        for (status, count) in self.statuses.iter() {
+            writeln!(f, "  {status}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use super::Stats;
+    use crate::testing::graph;
+
+    const PLAN: &str = indoc! {"
+        2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+        2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email
+        2023-06-30T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB Email->EmailRelease
+        2022-06-01T00:00:00.000Z 2022-07-31T00:00:00.000Z program2 Complete TeamC null->Legacy
+    "};
+
+    #[test]
+    fn test_counts() {
+        let stats = Stats::from(&graph(PLAN));
+
+        assert_eq!(stats.programs, 2);
+        assert_eq!(stats.features, 4);
+        assert_eq!(stats.milestones, 1);
+        assert_eq!(stats.max_depth, 2);
+    }
+
+    #[test]
+    fn test_breakdowns() {
+        let stats = Stats::from(&graph(PLAN));
+
+        assert_eq!(stats.teams["TeamB"], 2);
+        assert_eq!(stats.statuses["Complete"], 3);
+        assert_eq!(
+            stats.start_date.map(|date| date.to_rfc3339()),
+            Some("2022-06-01T00:00:00+00:00".to_string())
+        );
+    }
+}
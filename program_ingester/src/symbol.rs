@@ -0,0 +1,208 @@
+//! Shared strings for the names that repeat across features: IDs, teams, statuses and programs.
+//!
+//! A [Symbol] is a reference counted string, so cloning it (eg: from a [RawFeature](crate::input::RawFeature)
+//! into a [Feature](crate::output::Feature)) does not copy the text. An [Interner] hands out the same [Symbol]
+//! for equal strings, so a team named on thousands of lines is stored once.
+//!
+//! Symbols are serialised as plain strings, so the output is the same as with [String]s.
+use std::{borrow::Borrow, collections::HashSet, fmt::Display, ops::Deref, sync::Arc};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An immutable string that is cheap to clone
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Look up maps keyed by [Symbol] with a `&str`
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Symbol(text.into())
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Symbol(text.into())
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(text: &String) -> Self {
+        Symbol(text.as_str().into())
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+/// Print the text, like a [String]
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&*self.0, f)
+    }
+}
+
+/// Debug as the text, like a [String]
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Symbol::from)
+    }
+}
+
+/// Hands out one shared [Symbol] per distinct string
+#[derive(Debug, Default)]
+pub struct Interner {
+    symbols: HashSet<Symbol>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// The symbol for the text, only allocated the first time the text is seen
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        match self.symbols.get(text) {
+            Some(symbol) => symbol.clone(),
+            None => {
+                let symbol = Symbol::from(text);
+                self.symbols.insert(symbol.clone());
+                symbol
+            }
+        }
+    }
+
+    /// Replace a symbol with the shared one for its text, eg: after merging symbols from another interner
+    pub fn share(&mut self, symbol: &mut Symbol) {
+        match self.symbols.get(symbol.as_str()) {
+            Some(shared) => *symbol = shared.clone(),
+            None => {
+                self.symbols.insert(symbol.clone());
+            }
+        }
+    }
+
+    /// The number of distinct strings
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use std::sync::Arc;
+
+    use super::{Interner, Symbol};
+    use crate::{
+        output::ProgramGraph,
+        testing::{features, graph},
+    };
+
+    const PLAN: &str = indoc! {"
+        2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite
+        2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Email
+        2023-02-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Chat
+    "};
+
+    #[test]
+    fn test_symbols_are_shared() {
+        let mut interner = Interner::new();
+        let team = interner.intern("TeamB");
+        assert!(Arc::ptr_eq(&team.0, &interner.intern("TeamB").0));
+
+        let mut other = Symbol::from("TeamB");
+        interner.share(&mut other);
+        assert!(Arc::ptr_eq(&team.0, &other.0));
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn test_ingested_names_are_shared() {
+        let features = features(PLAN);
+        let [_, email, chat] = &features[..] else {
+            panic!("there should be 3 features");
+        };
+        assert!(Arc::ptr_eq(&email.assigned_team.0, &chat.assigned_team.0));
+        assert!(Arc::ptr_eq(
+            &email.parent_id.as_ref().unwrap().0,
+            &chat.parent_id.as_ref().unwrap().0
+        ));
+    }
+
+    #[test]
+    fn test_symbols_serialise_as_strings() {
+        let graph = graph(PLAN);
+        let json = serde_json::to_value(&graph).expect("graph should serialise");
+        assert_eq!(json["programs"][0]["id"], "program1");
+        assert_eq!(
+            json["programs"][0]["root"]["subfeatures"][0]["assigned_team"],
+            "TeamB"
+        );
+        let back: ProgramGraph = serde_json::from_value(json).expect("graph should deserialise");
+        assert_eq!(back, graph);
+    }
+}
@@ -0,0 +1,19 @@
+//! Fixtures shared by the unit tests.
+use std::io::BufReader;
+
+use crate::{
+    input::{Ingester, RawFeature},
+    output::ProgramGraph,
+};
+
+/// The features of a plan in the line format, eg: an `indoc!` block, read from the source named `input`
+pub(crate) fn features(input: &str) -> Vec<RawFeature> {
+    Ingester::try_from(BufReader::new(input.trim().as_bytes()))
+        .expect("test input should be checked")
+        .features
+}
+
+/// The graph of a plan in the line format
+pub(crate) fn graph(input: &str) -> ProgramGraph {
+    ProgramGraph::from(features(input))
+}
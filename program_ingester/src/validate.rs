@@ -0,0 +1,279 @@
+//! Check ingested [RawFeature]s for problems that would produce a broken or misleading [ProgramGraph](crate::output::ProgramGraph).
+//!
+//! Zero length features are milestones (see [FeatureKind](crate::input::FeatureKind)), so they are not flagged.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use serde::Serialize;
+
+use crate::{
+    input::{FeatureID, Provenance, RawFeature},
+    symbol::Symbol,
+};
+
+/// Something wrong with a feature
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum Problem {
+    /// The end_date is before the start_date (zero length features are milestones, and are not flagged)
+    EndBeforeStart,
+    /// Another feature has the same ID
+    DuplicateId {
+        /// Where the first feature with this ID was read from
+        #[serde(skip_serializing_if = "Option::is_none")]
+        first_defined: Option<Provenance>,
+    },
+    /// The parent feature is never defined, so this feature is left out of the graph
+    MissingParent { parent_id: FeatureID },
+    /// Following the parents never leads to a root (they form a cycle, or lead to a missing parent),
+    /// so this feature is left out of the graph
+    Unreachable,
+    /// The feature starts before, or ends after, its parent
+    OutsideParent { parent_id: FeatureID },
+    /// The feature's program_id differs from its parent's
+    ProgramMismatch {
+        parent_id: FeatureID,
+        parent_program_id: Symbol,
+    },
+}
+
+/// A problem found with a feature
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Issue {
+    #[serde(rename = "feature")]
+    pub feature_id: FeatureID,
+    #[serde(flatten)]
+    pub problem: Problem,
+    /// Where the feature was read from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(provenance) = &self.provenance {
+            write!(f, "{provenance}: ")?;
+        }
+        let feature_id = &self.feature_id;
+        match &self.problem {
+            Problem::EndBeforeStart => write!(f, "{feature_id}: ends before it starts"),
+            Problem::DuplicateId {
+                first_defined: Some(first_defined),
+            } => write!(
+                f,
+                "{feature_id}: is defined more than once (first at {first_defined})"
+            ),
+            Problem::DuplicateId { first_defined: None } => {
+                write!(f, "{feature_id}: is defined more than once")
+            }
+            Problem::MissingParent { parent_id } => {
+                write!(f, "{feature_id}: parent {parent_id} is not defined")
+            }
+            Problem::Unreachable => write!(
+                f,
+                "{feature_id}: is not under a root, its parents form a cycle or lead to a missing parent"
+            ),
+            Problem::OutsideParent { parent_id } => {
+                write!(f, "{feature_id}: is scheduled outside of its parent {parent_id}")
+            }
+            Problem::ProgramMismatch {
+                parent_id,
+                parent_program_id,
+            } => write!(
+                f,
+                "{feature_id}: is in a different program than its parent {parent_id} ({parent_program_id})"
+            ),
+        }
+    }
+}
+
+/// Find the problems with the features, in input order
+pub fn validate(features: &[RawFeature]) -> Vec<Issue> {
+    let mut issues = vec![];
+    let mut seen: HashMap<&FeatureID, &RawFeature> = HashMap::new();
+    let by_id: HashMap<&FeatureID, &RawFeature> = features
+        .iter()
+        .map(|feature| (&feature.id, feature))
+        .collect();
+    let reachable = reachable_from_roots(by_id.values().copied());
+
+    for feature in features.iter() {
+        let mut flag = |problem| {
+            issues.push(Issue {
+                feature_id: feature.id.clone(),
+                problem,
+                provenance: feature.provenance.clone(),
+            })
+        };
+
+        if let Some(first) = seen.get(&feature.id) {
+            flag(Problem::DuplicateId {
+                first_defined: first.provenance.clone(),
+            });
+        } else {
+            seen.insert(&feature.id, feature);
+        }
+
+        if feature.end_date < feature.start_date {
+            flag(Problem::EndBeforeStart);
+        }
+
+        if let Some(parent_id) = &feature.parent_id {
+            match by_id.get(parent_id) {
+                None => flag(Problem::MissingParent {
+                    parent_id: parent_id.clone(),
+                }),
+                Some(parent) => {
+                    if !reachable.contains(&feature.id) {
+                        flag(Problem::Unreachable);
+                    }
+                    if feature.start_date < parent.start_date || feature.end_date > parent.end_date
+                    {
+                        flag(Problem::OutsideParent {
+                            parent_id: parent_id.clone(),
+                        });
+                    }
+                    if feature.program_id != parent.program_id {
+                        flag(Problem::ProgramMismatch {
+                            parent_id: parent_id.clone(),
+                            parent_program_id: parent.program_id.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+// the IDs of the roots, and of the features under them
+fn reachable_from_roots<'a>(
+    features: impl Iterator<Item = &'a RawFeature>,
+) -> HashSet<&'a FeatureID> {
+    let mut children: HashMap<&FeatureID, Vec<&FeatureID>> = HashMap::new();
+    let mut stack = vec![];
+    for feature in features {
+        match &feature.parent_id {
+            Some(parent_id) => children.entry(parent_id).or_default().push(&feature.id),
+            None => stack.push(&feature.id),
+        }
+    }
+
+    let mut reachable = HashSet::new();
+    while let Some(feature_id) = stack.pop() {
+        if reachable.insert(feature_id) {
+            stack.extend(children.get(feature_id).into_iter().flatten());
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use super::{validate, Problem};
+    use crate::testing::features;
+
+    const ROOT: &str = "2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite";
+
+    // the problems found in the plan, after its root feature
+    fn problems(plan: &str) -> Vec<(String, Problem)> {
+        validate(&features(&format!("{ROOT}\n{plan}")))
+            .into_iter()
+            .map(|issue| (issue.feature_id.to_string(), issue.problem))
+            .collect()
+    }
+
+    #[test]
+    fn test_end_before_start() {
+        let plan = "2023-06-30T00:00:00.000Z 2023-01-01T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Backwards";
+        assert_eq!(
+            problems(plan),
+            [("Backwards".to_string(), Problem::EndBeforeStart)]
+        );
+    }
+
+    #[test]
+    fn test_outside_parent() {
+        let plan = "2023-01-01T00:00:00.000Z 2024-06-30T00:00:00.000Z program2 Complete TeamB ProductivitySuite->Overrun";
+        assert_eq!(
+            problems(plan),
+            [
+                (
+                    "Overrun".to_string(),
+                    Problem::OutsideParent {
+                        parent_id: "ProductivitySuite".into()
+                    }
+                ),
+                (
+                    "Overrun".to_string(),
+                    Problem::ProgramMismatch {
+                        parent_id: "ProductivitySuite".into(),
+                        parent_program_id: "program1".into()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let plan = indoc! {"
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamC ProductivitySuite->Email
+        "};
+        let features = features(&format!("{ROOT}\n{plan}"));
+        let issues = validate(&features);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].problem,
+            Problem::DuplicateId {
+                first_defined: features[1].provenance.clone()
+            }
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "input:3: Email: is defined more than once (first at input:2)"
+        );
+    }
+
+    #[test]
+    fn test_features_outside_the_graph() {
+        let plan = indoc! {"
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB Missing->Orphan
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamC Orphan->Stray
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamD Egg->Chicken
+            2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamD Chicken->Egg
+        "};
+        assert_eq!(
+            problems(plan),
+            [
+                (
+                    "Orphan".to_string(),
+                    Problem::MissingParent {
+                        parent_id: "Missing".into()
+                    }
+                ),
+                ("Stray".to_string(), Problem::Unreachable),
+                ("Chicken".to_string(), Problem::Unreachable),
+                ("Egg".to_string(), Problem::Unreachable),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_milestones_are_not_flagged() {
+        let plan = indoc! {"
+            2023-06-30T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Release
+            2023-12-31T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamB ProductivitySuite->Launch milestone
+        "};
+        let features = features(&format!("{ROOT}\n{plan}"));
+
+        assert!(features[1..].iter().all(|feature| feature.is_milestone()));
+        assert_eq!(validate(&features), vec![]);
+    }
+}
@@ -0,0 +1,182 @@
+//! Work breakdown structure (WBS) codes, the outline numbers project managers refer to work by, eg: `1.2.3`.
+//!
+//! Programs are numbered by the start_date of their root, and the subfeatures of a feature by their start_date
+//! (with the ID to break ties), so `1.2.3` is the 3rd subfeature of the 2nd subfeature of the 1st program's root.
+//! [ProgramGraph::from] numbers every feature, see [Feature::wbs].
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    errors::ProgramIngesterError,
+    output::{Feature, Program, ProgramGraph},
+};
+
+/// A WBS code, eg: `1.2.3`. Codes are ordered like an outline: `1.2` comes before `1.2.1`, which comes before `1.10`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WbsCode(Vec<usize>);
+
+impl WbsCode {
+    /// The numbers of the code, from the program down, starting at 1
+    pub fn levels(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// The code of the parent feature, `None` for the root of a program
+    pub fn parent(&self) -> Option<WbsCode> {
+        match self.0.len() {
+            0 | 1 => None,
+            len => Some(WbsCode(self.0[..len - 1].to_vec())),
+        }
+    }
+
+    // the code of the nth subfeature, starting at 1
+    fn child(&self, number: usize) -> WbsCode {
+        WbsCode([&self.0[..], &[number]].concat())
+    }
+}
+
+impl Display for WbsCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let levels: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        write!(f, "{}", levels.join("."))
+    }
+}
+
+impl FromStr for WbsCode {
+    type Err = ProgramIngesterError;
+
+    /// Parse a code like `1.2.3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split('.')
+            .map(|level| match level.parse::<usize>() {
+                Ok(number) if number > 0 => Ok(number),
+                _ => Err(ProgramIngesterError::InvalidProgramInput(format!(
+                    "The WBS code '{s}' needs to be numbers from 1, separated by '.'"
+                ))),
+            })
+            .collect::<Result<_, _>>()
+            .map(WbsCode)
+    }
+}
+
+/// Serialise as the `1.2.3` form
+impl Serialize for WbsCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for WbsCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl ProgramGraph {
+    /// Give every feature its [WbsCode], replacing the codes it had
+    pub fn number_wbs(&mut self) {
+        fn number(feature: &mut Feature, code: WbsCode) {
+            let mut order: Vec<usize> = (0..feature.subfeatures.len()).collect();
+            order.sort_by(|&a, &b| {
+                let (a, b) = (&feature.subfeatures[a], &feature.subfeatures[b]);
+                (a.start_date, &a.id).cmp(&(b.start_date, &b.id))
+            });
+            for (position, index) in order.into_iter().enumerate() {
+                number(&mut feature.subfeatures[index], code.child(position + 1));
+            }
+            feature.wbs = Some(code);
+        }
+
+        let mut order: Vec<usize> = (0..self.programs.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.programs[a], &self.programs[b]);
+            (a.root.start_date, &a.id).cmp(&(b.root.start_date, &b.id))
+        });
+        for (position, index) in order.into_iter().enumerate() {
+            number(&mut self.programs[index].root, WbsCode(vec![position + 1]));
+        }
+    }
+
+    /// Find a feature by its WBS code
+    pub fn by_wbs(&self, code: &WbsCode) -> Option<(&Program, &Feature)> {
+        self.programs.iter().find_map(|program| {
+            program
+                .root
+                .flatten()
+                .into_iter()
+                .find(|feature| feature.wbs.as_ref() == Some(code))
+                .map(|feature| (program, feature))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use std::str::FromStr;
+
+    use super::WbsCode;
+    use crate::testing::graph;
+
+    const PLAN: &str = indoc! {"
+        2023-02-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program2 InProgress TeamD null->Platform
+        2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->Suite
+        2023-03-01T00:00:00.000Z 2023-04-30T00:00:00.000Z program1 InProgress TeamB Suite->Chat
+        2023-01-01T00:00:00.000Z 2023-02-28T00:00:00.000Z program1 Complete TeamB Suite->Email
+        2023-01-10T00:00:00.000Z 2023-01-20T00:00:00.000Z program1 Complete TeamC Email->Search
+        2023-01-01T00:00:00.000Z 2023-01-09T00:00:00.000Z program1 Complete TeamC Email->Compose
+    "};
+
+    #[test]
+    fn test_wbs_numbering() {
+        let graph = graph(PLAN);
+        let code = |id: &str| {
+            graph
+                .programs
+                .iter()
+                .flat_map(|program| program.root.flatten())
+                .find(|feature| feature.id == id)
+                .and_then(|feature| feature.wbs.as_ref())
+                .map(ToString::to_string)
+        };
+        assert_eq!(code("Suite").as_deref(), Some("1"));
+        assert_eq!(code("Email").as_deref(), Some("1.1"));
+        assert_eq!(code("Compose").as_deref(), Some("1.1.1"));
+        assert_eq!(code("Search").as_deref(), Some("1.1.2"));
+        assert_eq!(code("Chat").as_deref(), Some("1.2"));
+        assert_eq!(code("Platform").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_lookup_by_wbs() {
+        let graph = graph(PLAN);
+        let search = WbsCode::from_str("1.1.2").expect("the code should parse");
+        let (program, feature) = graph.by_wbs(&search).expect("1.1.2 should exist");
+        assert_eq!(
+            (program.id.as_str(), feature.id.as_str()),
+            ("program1", "Search")
+        );
+        assert!(graph.by_wbs(&WbsCode::from_str("3").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_wbs_codes() {
+        let search = WbsCode::from_str("1.1.2").expect("the code should parse");
+        assert_eq!(
+            search.parent().map(|code| code.to_string()).as_deref(),
+            Some("1.1")
+        );
+        assert!(WbsCode::from_str("1.0").is_err());
+        assert!(WbsCode::from_str("1.1").unwrap() < WbsCode::from_str("1.1.1").unwrap());
+    }
+
+    #[test]
+    fn test_wbs_serialisation() {
+        let json = serde_json::to_value(graph(PLAN)).expect("graph should serialise");
+        assert_eq!(json["programs"][0]["root"]["wbs"], "1");
+        assert_eq!(json["programs"][0]["root"]["subfeatures"][0]["wbs"], "1.1");
+    }
+}
@@ -1,12 +1,59 @@
-#[test]
-#[ignore]
-fn from_stdin() {
-    // https://stackoverflow.com/a/28370712/8148609
-    assert!(false)
+use std::{fs, path::PathBuf};
+
+use program_ingester::{input::Ingester, output::ProgramGraph};
+
+// a fresh directory for the test's input files
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("program_ingester_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("the temp dir should be writable");
+    dir
 }
 
+// reading from STDIN is tested by running the CLI, in cli/tests/stdin.rs
+
 #[test]
-#[ignore]
 fn from_file() {
-    assert!(false)
+    let dir = scratch_dir("from_file");
+    fs::write(
+        dir.join("a.txt"),
+        "2023-01-01T00:00:00.000Z 2023-12-31T00:00:00.000Z program1 InProgress TeamA null->ProductivitySuite\n",
+    )
+    .expect("the test file should be written");
+    fs::write(
+        dir.join("b.txt"),
+        "2023-01-01T00:00:00.000Z 2023-06-30T00:00:00.000Z program1 Complete TeamB ProductivitySuite->Email\n\
+         2023-02-01T00:00:00.000Z 2023-03-31T00:00:00.000Z program1 Complete TeamC ProductivitySuite->Chat\n",
+    )
+    .expect("the test file should be written");
+
+    // a directory, a glob and a file that name the same files read each of them once
+    for inputs in [
+        vec![dir.clone()],
+        vec![dir.join("*.txt")],
+        vec![dir.clone(), dir.join("*.txt"), dir.join("b.txt")],
+    ] {
+        let ingester = Ingester::from_paths(&inputs).expect("the files should be ingested");
+        assert_eq!(ingester.features.len(), 3);
+
+        let chat = &ingester.features[2];
+        let provenance = chat.provenance.as_ref().expect("files are named");
+        assert_eq!(provenance.source, dir.join("b.txt").display().to_string());
+        assert_eq!(provenance.line, 2);
+
+        // the parent in a.txt is found for the features in b.txt
+        let graph = ProgramGraph::from(ingester.features);
+        assert_eq!(graph.programs.len(), 1);
+        assert_eq!(graph.programs[0].root.subfeatures.len(), 2);
+    }
+
+    fs::write(dir.join("c.txt"), "not a feature\n").expect("the test file should be written");
+    let error = Ingester::from_paths(&[dir.join("a.txt"), dir.join("c.txt")])
+        .err()
+        .expect("c.txt is not valid");
+    assert!(error
+        .to_string()
+        .starts_with(&format!("{}:1: ", dir.join("c.txt").display())));
+
+    fs::remove_dir_all(&dir).expect("the temp dir should be removed");
 }